@@ -1,49 +1,188 @@
-use crate::collector::{MemorySnapshot, ProcessMemory, SystemMemory, NumaNode};
-use std::collections::HashMap;
+use crate::collector::{
+    MemoryPressure, MemorySnapshot, NumaNode, ProcessMemory, ShmSegment, SystemMemory, Vmstat,
+    Zswap,
+};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::time::Instant;
+use crate::os_utils;
 
-#[derive(Debug, Clone)]
+pub const DEFAULT_HISTORY_LEN: usize = 60;
+const LEAK_WINDOW: usize = 10;
+// Fewer samples than the leak window would never flag a leak; the upper bound
+// caps the cost at 8 bytes per sample per process.
+pub const MIN_HISTORY_LEN: usize = LEAK_WINDOW;
+pub const MAX_HISTORY_LEN: usize = 3600;
+// Five minutes at the default interval, independent of --history.
+pub const SYSTEM_HISTORY_LEN: usize = 300;
+pub const SUMMARY_TOP_PROCESSES: usize = 5;
+
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct AnalyzedState {
     pub processes: Vec<ProcessStats>,
     pub system: SystemStats,
     pub shared_memory: SharedMemoryStats,
     pub numa_nodes: Vec<NumaNode>,
     pub memory_map: MemoryMap,
+    pub groups: Vec<GroupedStats>,
+    pub users: Vec<GroupedStats>,
+    pub processes_skipped: usize,
+    pub processes_unreadable: usize,
+    pub processes_total: usize,
+    #[serde(skip)]
+    pub used_pct_history: Vec<f64>,
+    #[serde(skip)]
+    pub process_count_history: Vec<usize>,
+    #[serde(skip)]
+    pub tree: Vec<TreeRow>,
 }
 
-#[derive(Debug, Clone)]
+impl AnalyzedState {
+    // Keeps the first top processes in the given order, as the TUI would
+    // list them; 0 keeps all. processes_total still counts every process
+    // analyzed.
+    pub fn limit_processes(&mut self, top: usize, sort: impl FnOnce(&mut [ProcessStats])) {
+        if top == 0 {
+            return;
+        }
+
+        sort(&mut self.processes);
+        self.processes.truncate(top);
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct ProcessStats {
     pub pid: u32,
     pub name: String,
+    pub cmdline: String,
+    pub exe: String,
+    pub start_time_ticks: u64,
+    pub age_secs: u64,
     pub pss_kb: u64,
+    pub mem_pct: f64,
+    pub sharing_pct: f64,
     pub rss_kb: u64,
     pub shared_kb: u64,
     pub private_kb: u64,
+    pub shared_clean_kb: u64,
+    pub shared_dirty_kb: u64,
+    pub private_clean_kb: u64,
+    pub private_dirty_kb: u64,
     pub swap_kb: u64,
+    pub swap_pss_kb: u64,
+    pub anonymous_kb: u64,
+    pub anon_huge_kb: u64,
+    pub file_backed_kb: u64,
+    pub oom_score: i32,
+    pub oom_score_adj: i32,
+    pub cgroup: String,
+    pub uid: u32,
+    pub username: String,
+    pub threads: u32,
+    pub is_kernel_thread: bool,
     pub pss_delta_kb: i64,
+    pub growth_rate_kb_s: f64,
+    pub minor_faults: u64,
+    pub major_faults: u64,
+    pub minor_faults_delta: u64,
+    pub major_faults_delta: u64,
+    pub major_fault_rate: f64,
+    pub wx_regions: u32,
+    pub wx_kb: u64,
+    pub peak_pss_kb: u64,
+    pub min_pss_kb: u64,
+    pub avg_pss_kb: u64,
+    pub numa_kb: BTreeMap<u32, u64>,
+    pub suspected_leak: bool,
+    pub rss_only: bool,
+    pub ppid: u32,
+    #[serde(skip)]
+    pub pss_history: Vec<u64>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct GroupedStats {
+    pub name: String,
+    pub count: usize,
+    pub pss_kb: u64,
+    pub rss_kb: u64,
+    pub shared_kb: u64,
+    pub private_kb: u64,
+    pub swap_kb: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct SystemStats {
     pub total_kb: u64,
     pub used_kb: u64,
     pub available_kb: u64,
+    pub free_kb: u64,
     pub cached_kb: u64,
     pub buffers_kb: u64,
     pub swap_total_kb: u64,
     pub swap_used_kb: u64,
     pub total_process_pss_kb: u64,
     pub total_process_rss_kb: u64,
+    pub total_process_swap_pss_kb: u64,
+    pub total_process_anon_kb: u64,
+    pub total_process_anon_huge_kb: u64,
+    pub total_process_file_kb: u64,
+    pub pressure: Option<MemoryPressure>,
+    pub cgroup_limited: bool,
+    pub host_total_kb: u64,
+    pub zswap: Option<Zswap>,
+    pub swap_rates: Option<SwapRates>,
+    pub process_count: usize,
+    pub commit: Option<CommitStats>,
 }
 
-#[derive(Debug, Clone)]
+// Committed_AS against CommitLimit. The limit is only enforced in
+// overcommit mode 2; in modes 0 and 1 going over it means relying on the
+// OOM killer rather than failing allocations.
+#[derive(Debug, Clone, Serialize)]
+pub struct CommitStats {
+    pub limit_kb: u64,
+    pub committed_kb: u64,
+    pub ratio_pct: f64,
+    pub overcommit_memory: Option<u32>,
+}
+
+impl CommitStats {
+    fn from_system(system: &SystemMemory) -> Option<Self> {
+        (system.commit_limit_kb > 0).then(|| CommitStats {
+            limit_kb: system.commit_limit_kb,
+            committed_kb: system.committed_as_kb,
+            ratio_pct: system.committed_as_kb as f64 / system.commit_limit_kb as f64 * 100.0,
+            overcommit_memory: system.overcommit_memory,
+        })
+    }
+
+    pub fn exceeded(&self) -> bool {
+        self.committed_kb > self.limit_kb
+    }
+}
+
+// Pages per second over the last tick, from the /proc/vmstat counters.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SwapRates {
+    pub swap_in_per_s: f64,
+    pub swap_out_per_s: f64,
+    pub major_faults_per_s: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct SharedMemoryStats {
     pub total_shared_kb: u64,
     pub total_shared_clean_kb: u64,
     pub total_shared_dirty_kb: u64,
     pub sharing_efficiency: f64,
+    pub shmem_kb: u64,
+    pub sysv_shm_kb: u64,
+    pub shm_segments: Vec<ShmSegment>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct MemoryMap {
     pub kernel_kb: u64,
     pub process_private_kb: u64,
@@ -53,90 +192,459 @@ pub struct MemoryMap {
     pub free_kb: u64,
     pub slab_kb: u64,
     pub page_tables_kb: u64,
+    pub hugepages_used_kb: u64,
+    pub hugepages_free_kb: u64,
+    pub anon_hugepages_kb: u64,
+    pub dirty_kb: u64,
+    pub writeback_kb: u64,
+    pub process_pss_kb: u64,
+    pub anon_mapped_kb: u64,
+    pub reconciliation_error_kb: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionSummary {
+    pub duration_secs: f64,
+    pub samples: u64,
+    pub peak_used_kb: u64,
+    pub peak_used_pct: f64,
+    pub total_kb: u64,
+    pub top_processes: Vec<SessionProcess>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionProcess {
+    pub pid: u32,
+    pub name: String,
+    pub peak_pss_kb: u64,
 }
 
+// Per-process history is keyed by PID and start time: Linux reuses PIDs, and
+// a new process under a dead one's PID must start with no delta or peak.
+pub(crate) type ProcessKey = (u32, u64);
+
 pub struct Analyzer {
-    last_snapshot: Option<MemorySnapshot>,
-    process_history: HashMap<u32, u64>,
+    process_history: HashMap<ProcessKey, u64>,
+    fault_history: HashMap<ProcessKey, (u64, u64)>,
+    session_pss: HashMap<ProcessKey, PssAggregate>,
+    pss_history: HashMap<ProcessKey, VecDeque<u64>>,
+    sample_times: VecDeque<Instant>,
+    history_len: usize,
+    used_pct_history: VecDeque<f64>,
+    process_count_history: VecDeque<usize>,
+    last_vmstat: Option<(Vmstat, Instant)>,
+    clock_ticks: u64,
+    started: Instant,
+    samples: u64,
+    peak_used: Option<(u64, u64)>,
+    session_top: Vec<SessionProcess>,
 }
 
 impl Analyzer {
-    pub fn new() -> Self {
+    pub fn new(history_len: usize) -> Self {
         Self {
-            last_snapshot: None,
             process_history: HashMap::new(),
+            fault_history: HashMap::new(),
+            session_pss: HashMap::new(),
+            pss_history: HashMap::new(),
+            sample_times: VecDeque::with_capacity(history_len),
+            history_len: history_len.max(1),
+            used_pct_history: VecDeque::with_capacity(SYSTEM_HISTORY_LEN),
+            process_count_history: VecDeque::with_capacity(SYSTEM_HISTORY_LEN),
+            last_vmstat: None,
+            clock_ticks: os_utils::clock_ticks_per_sec(),
+            started: Instant::now(),
+            samples: 0,
+            peak_used: None,
+            session_top: Vec::new(),
         }
     }
 
-    pub fn update(&mut self, snapshot: MemorySnapshot) {
-        self.last_snapshot = Some(snapshot);
-    }
+    pub fn set_history_len(&mut self, history_len: usize) {
+        self.history_len = history_len.max(1);
 
-    pub fn get_state(&mut self) -> AnalyzedState {
-        let snapshot = match &self.last_snapshot {
-            Some(s) => s.clone(),
-            None => return self.empty_state(),
-        };
+        while self.sample_times.len() > self.history_len {
+            self.sample_times.pop_front();
+        }
 
-        let processes = self.analyze_processes(&snapshot.processes);
-        let system = self.analyze_system(&snapshot.system, &snapshot.processes);
-        let shared_memory = self.analyze_shared_memory(&snapshot.processes);
-        let memory_map = self.build_memory_map(&snapshot.system, &snapshot.processes);
+        for samples in self.pss_history.values_mut() {
+            while samples.len() > self.history_len {
+                samples.pop_front();
+            }
+        }
+    }
+
+    // Takes the snapshot by value: the aggregate passes borrow it, then the
+    // process list is consumed so names and cmdlines move instead of cloning.
+    pub fn analyze(&mut self, snapshot: MemorySnapshot) -> AnalyzedState {
+        let mut system =
+            self.analyze_system(&snapshot.system, &snapshot.processes, &snapshot.pressure);
+        system.swap_rates = self.swap_rates(snapshot.vmstat);
+        let used_pct_history = self.record_used_pct(&system);
+        let process_count_history = push_capped(&mut self.process_count_history, system.process_count);
+        let shared_memory =
+            self.analyze_shared_memory(&snapshot.system, &snapshot.processes, snapshot.shm_segments);
+        let complete = !snapshot.pid_filtered
+            && snapshot.processes_skipped == 0
+            && snapshot.processes_unreadable == 0;
+        let memory_map = self.build_memory_map(&snapshot.system, &snapshot.processes, complete);
+        let processes =
+            self.analyze_processes(snapshot.processes, snapshot.uptime_secs, system.total_kb);
+        let groups = self.group_by(&processes, |p| &p.name);
+        let users = self.group_by(&processes, |p| &p.username);
+        let tree = build_tree(&processes);
+        self.record_session(&system, &processes);
 
         AnalyzedState {
+            processes_total: processes.len(),
             processes,
             system,
             shared_memory,
             numa_nodes: snapshot.numa_nodes,
             memory_map,
+            groups,
+            users,
+            processes_skipped: snapshot.processes_skipped,
+            processes_unreadable: snapshot.processes_unreadable,
+            used_pct_history,
+            process_count_history,
+            tree,
         }
     }
 
-    fn analyze_processes(&mut self, processes: &[ProcessMemory]) -> Vec<ProcessStats> {
+    pub fn summary(&self) -> SessionSummary {
+        let (peak_used_kb, total_kb) = self.peak_used.unwrap_or((0, 0));
+        SessionSummary {
+            duration_secs: self.started.elapsed().as_secs_f64(),
+            samples: self.samples,
+            peak_used_kb,
+            peak_used_pct: if total_kb > 0 {
+                peak_used_kb as f64 / total_kb as f64 * 100.0
+            } else {
+                0.0
+            },
+            total_kb,
+            top_processes: self.session_top.clone(),
+        }
+    }
+
+    // Only the leaders are kept: a process that fell out of the top and later
+    // climbs back in must have beaten its old peak to do so.
+    fn record_session(&mut self, system: &SystemStats, processes: &[ProcessStats]) {
+        self.samples += 1;
+        if self.peak_used.is_none_or(|(used, _)| system.used_kb > used) {
+            self.peak_used = Some((system.used_kb, system.total_kb));
+        }
+
+        for proc in processes {
+            match self.session_top.iter_mut().find(|p| p.pid == proc.pid && p.name == proc.name) {
+                Some(entry) => entry.peak_pss_kb = entry.peak_pss_kb.max(proc.peak_pss_kb),
+                None => self.session_top.push(SessionProcess {
+                    pid: proc.pid,
+                    name: proc.name.clone(),
+                    peak_pss_kb: proc.peak_pss_kb,
+                }),
+            }
+        }
+        self.session_top
+            .sort_by_key(|p| (std::cmp::Reverse(p.peak_pss_kb), p.pid));
+        self.session_top.truncate(SUMMARY_TOP_PROCESSES);
+    }
+
+    fn swap_rates(&mut self, vmstat: Option<Vmstat>) -> Option<SwapRates> {
+        let now = Instant::now();
+        let current = vmstat?;
+        let (last, last_at) = self.last_vmstat.replace((current.clone(), now))?;
+
+        // The counters only grow until a reboot, e.g. between two parts of a
+        // replayed recording; skip that tick rather than report a bogus rate.
+        if current.pswpin < last.pswpin
+            || current.pswpout < last.pswpout
+            || current.pgmajfault < last.pgmajfault
+        {
+            return None;
+        }
+
+        let secs = now.duration_since(last_at).as_secs_f64();
+        if secs <= 0.0 {
+            return None;
+        }
+
+        Some(SwapRates {
+            swap_in_per_s: (current.pswpin - last.pswpin) as f64 / secs,
+            swap_out_per_s: (current.pswpout - last.pswpout) as f64 / secs,
+            major_faults_per_s: (current.pgmajfault - last.pgmajfault) as f64 / secs,
+        })
+    }
+
+    fn record_used_pct(&mut self, system: &SystemStats) -> Vec<f64> {
+        let pct = if system.total_kb > 0 {
+            system.used_kb as f64 / system.total_kb as f64 * 100.0
+        } else {
+            0.0
+        };
+
+        push_capped(&mut self.used_pct_history, pct)
+    }
+
+    // Recordings made before uptime was captured have none, so no age.
+    fn age_secs(&self, start_time_ticks: u64, uptime_secs: f64) -> u64 {
+        if uptime_secs <= 0.0 {
+            return 0;
+        }
+
+        (uptime_secs as u64).saturating_sub(start_time_ticks / self.clock_ticks)
+    }
+
+    // total_kb is the header's total, so inside a limited cgroup the
+    // percentages are of the limit.
+    fn analyze_processes(
+        &mut self,
+        processes: Vec<ProcessMemory>,
+        uptime_secs: f64,
+        total_kb: u64,
+    ) -> Vec<ProcessStats> {
         let mut stats = Vec::with_capacity(processes.len());
         let mut new_history = HashMap::new();
+        let mut new_fault_history = HashMap::with_capacity(processes.len());
+        let mut new_session_pss = HashMap::with_capacity(processes.len());
+        let mut new_pss_history = HashMap::with_capacity(processes.len());
+
+        self.sample_times.push_back(Instant::now());
+        while self.sample_times.len() > self.history_len {
+            self.sample_times.pop_front();
+        }
+
+        let tick_secs = match self.sample_times.len() {
+            n if n >= 2 => self.sample_times[n - 1]
+                .duration_since(self.sample_times[n - 2])
+                .as_secs_f64(),
+            _ => 0.0,
+        };
 
         for proc in processes {
-            let last_pss = self.process_history.get(&proc.pid).copied().unwrap_or(proc.pss_kb);
+            let key = (proc.pid, proc.start_time_ticks);
+            let last_pss = self.process_history.get(&key).copied().unwrap_or(proc.pss_kb);
             let pss_delta = proc.pss_kb as i64 - last_pss as i64;
 
+            let mut samples = self
+                .pss_history
+                .remove(&key)
+                .unwrap_or_else(|| VecDeque::with_capacity(self.history_len));
+            samples.push_back(proc.pss_kb);
+            while samples.len() > self.history_len {
+                samples.pop_front();
+            }
+
+            let growth_rate = self.growth_rate(&samples);
+            let suspected_leak = is_monotonic_growth(&samples, LEAK_WINDOW);
+            let file_backed = file_backed_kb(&proc);
+            // Only PIDs seen this tick carry over, so dead PIDs drop their
+            // aggregates.
+            let session = match self.session_pss.get(&key) {
+                Some(agg) => agg.add(proc.pss_kb),
+                None => PssAggregate::new(proc.pss_kb),
+            };
+
+            let (last_minor, last_major) = self
+                .fault_history
+                .get(&key)
+                .copied()
+                .unwrap_or((proc.minor_faults, proc.major_faults));
+            let minor_delta = proc.minor_faults.saturating_sub(last_minor);
+            let major_delta = proc.major_faults.saturating_sub(last_major);
+            let major_rate = if tick_secs > 0.0 {
+                major_delta as f64 / tick_secs
+            } else {
+                0.0
+            };
+
             stats.push(ProcessStats {
                 pid: proc.pid,
-                name: proc.name.clone(),
+                name: proc.name,
+                cmdline: proc.cmdline,
+                exe: proc.exe,
+                start_time_ticks: proc.start_time_ticks,
+                age_secs: self.age_secs(proc.start_time_ticks, uptime_secs),
                 pss_kb: proc.pss_kb,
+                mem_pct: if total_kb > 0 {
+                    proc.pss_kb as f64 / total_kb as f64 * 100.0
+                } else {
+                    0.0
+                },
+                sharing_pct: sharing_pct(proc.rss_kb, proc.pss_kb),
                 rss_kb: proc.rss_kb,
                 shared_kb: proc.shared_clean_kb + proc.shared_dirty_kb,
                 private_kb: proc.private_clean_kb + proc.private_dirty_kb,
+                shared_clean_kb: proc.shared_clean_kb,
+                shared_dirty_kb: proc.shared_dirty_kb,
+                private_clean_kb: proc.private_clean_kb,
+                private_dirty_kb: proc.private_dirty_kb,
                 swap_kb: proc.swap_kb,
+                swap_pss_kb: proc.swap_pss_kb,
+                anonymous_kb: proc.anonymous_kb,
+                anon_huge_kb: proc.anon_huge_kb,
+                file_backed_kb: file_backed,
+                oom_score: proc.oom_score,
+                oom_score_adj: proc.oom_score_adj,
+                cgroup: proc.cgroup,
+                uid: proc.uid,
+                username: proc.username,
+                threads: proc.threads,
+                is_kernel_thread: proc.is_kernel_thread,
+                rss_only: proc.rss_only,
+                ppid: proc.ppid,
                 pss_delta_kb: pss_delta,
+                growth_rate_kb_s: growth_rate,
+                minor_faults: proc.minor_faults,
+                major_faults: proc.major_faults,
+                minor_faults_delta: minor_delta,
+                major_faults_delta: major_delta,
+                major_fault_rate: major_rate,
+                wx_regions: proc.wx_regions,
+                wx_kb: proc.wx_kb,
+                peak_pss_kb: session.max_kb,
+                min_pss_kb: session.min_kb,
+                avg_pss_kb: session.avg_kb(),
+                numa_kb: proc.numa_kb,
+                suspected_leak,
+                pss_history: samples.iter().copied().collect(),
             });
 
-            new_history.insert(proc.pid, proc.pss_kb);
+            new_history.insert(key, proc.pss_kb);
+            new_fault_history.insert(key, (proc.minor_faults, proc.major_faults));
+            new_session_pss.insert(key, session);
+            new_pss_history.insert(key, samples);
         }
 
         self.process_history = new_history;
+        self.fault_history = new_fault_history;
+        self.session_pss = new_session_pss;
+        self.pss_history = new_pss_history;
         stats
     }
 
-    fn analyze_system(&self, system: &SystemMemory, processes: &[ProcessMemory]) -> SystemStats {
+    // Samples for a PID always line up with the most recent ticks, so the
+    // oldest retained sample maps to sample_times[len - samples.len()].
+    fn growth_rate(&self, samples: &VecDeque<u64>) -> f64 {
+        let (Some(first), Some(last)) = (samples.front(), samples.back()) else {
+            return 0.0;
+        };
+
+        let start = self.sample_times.len().saturating_sub(samples.len());
+        let elapsed = match (self.sample_times.get(start), self.sample_times.back()) {
+            (Some(t0), Some(t1)) => t1.duration_since(*t0).as_secs_f64(),
+            _ => 0.0,
+        };
+
+        if elapsed > 0.0 {
+            (*last as f64 - *first as f64) / elapsed
+        } else {
+            0.0
+        }
+    }
+
+    fn group_by<F>(&self, processes: &[ProcessStats], key: F) -> Vec<GroupedStats>
+    where
+        F: Fn(&ProcessStats) -> &String,
+    {
+        let mut groups: HashMap<&str, GroupedStats> = HashMap::new();
+
+        for proc in processes {
+            let name = key(proc);
+            let group = groups
+                .entry(name.as_str())
+                .or_insert_with(|| GroupedStats {
+                    name: name.clone(),
+                    ..GroupedStats::default()
+                });
+
+            group.count += 1;
+            group.pss_kb += proc.pss_kb;
+            group.rss_kb += proc.rss_kb;
+            group.shared_kb += proc.shared_kb;
+            group.private_kb += proc.private_kb;
+            group.swap_kb += proc.swap_kb;
+        }
+
+        groups.into_values().collect()
+    }
+
+    fn analyze_system(
+        &self,
+        system: &SystemMemory,
+        processes: &[ProcessMemory],
+        pressure: &Option<MemoryPressure>,
+    ) -> SystemStats {
         let total_pss: u64 = processes.iter().map(|p| p.pss_kb).sum();
         let total_rss: u64 = processes.iter().map(|p| p.rss_kb).sum();
+        let total_swap_pss: u64 = processes.iter().map(|p| p.swap_pss_kb).sum();
+        let total_anon: u64 = processes.iter().map(|p| p.anonymous_kb).sum();
+        let total_anon_huge: u64 = processes.iter().map(|p| p.anon_huge_kb).sum();
+        let total_file: u64 = processes.iter().map(file_backed_kb).sum();
         let swap_used = system.swap_total_kb.saturating_sub(system.swap_free_kb);
 
+        // Inside a memory-limited cgroup the limit is the effective total;
+        // like docker stats, inactive file pages count as reclaimable cache.
+        if let Some(cg) = &system.cgroup_limit {
+            let used = cg.usage_kb.saturating_sub(cg.inactive_file_kb);
+
+            return SystemStats {
+                total_kb: cg.limit_kb,
+                used_kb: used,
+                available_kb: cg.limit_kb.saturating_sub(used),
+                free_kb: cg.limit_kb.saturating_sub(cg.usage_kb),
+                cached_kb: cg.usage_kb - used,
+                buffers_kb: 0,
+                swap_total_kb: system.swap_total_kb,
+                swap_used_kb: swap_used,
+                total_process_pss_kb: total_pss,
+                total_process_rss_kb: total_rss,
+                total_process_swap_pss_kb: total_swap_pss,
+                total_process_anon_kb: total_anon,
+                total_process_anon_huge_kb: total_anon_huge,
+                total_process_file_kb: total_file,
+                pressure: pressure.clone(),
+                cgroup_limited: true,
+                host_total_kb: system.total_kb,
+                zswap: system.zswap.clone(),
+                swap_rates: None,
+                commit: CommitStats::from_system(system),
+                process_count: processes.len(),
+            };
+        }
+
         SystemStats {
             total_kb: system.total_kb,
             used_kb: system.total_kb.saturating_sub(system.available_kb),
             available_kb: system.available_kb,
+            free_kb: system.free_kb,
             cached_kb: system.cached_kb,
             buffers_kb: system.buffers_kb,
             swap_total_kb: system.swap_total_kb,
             swap_used_kb: swap_used,
             total_process_pss_kb: total_pss,
             total_process_rss_kb: total_rss,
+            total_process_swap_pss_kb: total_swap_pss,
+            total_process_anon_kb: total_anon,
+            total_process_anon_huge_kb: total_anon_huge,
+            total_process_file_kb: total_file,
+            pressure: pressure.clone(),
+            cgroup_limited: false,
+            host_total_kb: system.total_kb,
+            zswap: system.zswap.clone(),
+            swap_rates: None,
+            commit: CommitStats::from_system(system),
+            process_count: processes.len(),
         }
     }
 
-    fn analyze_shared_memory(&self, processes: &[ProcessMemory]) -> SharedMemoryStats {
+    fn analyze_shared_memory(
+        &self,
+        system: &SystemMemory,
+        processes: &[ProcessMemory],
+        shm_segments: Vec<ShmSegment>,
+    ) -> SharedMemoryStats {
         let total_shared: u64 = processes
             .iter()
             .map(|p| p.shared_clean_kb + p.shared_dirty_kb)
@@ -145,24 +653,31 @@ impl Analyzer {
         let total_shared_clean: u64 = processes.iter().map(|p| p.shared_clean_kb).sum();
         let total_shared_dirty: u64 = processes.iter().map(|p| p.shared_dirty_kb).sum();
 
-        let total_rss: u64 = processes.iter().map(|p| p.rss_kb).sum();
-        let total_pss: u64 = processes.iter().map(|p| p.pss_kb).sum();
+        // RSS-only processes have no PSS; counting their RSS would read as
+        // everything being shared.
+        let with_pss = || processes.iter().filter(|p| !p.rss_only);
+        let total_rss: u64 = with_pss().map(|p| p.rss_kb).sum();
+        let total_pss: u64 = with_pss().map(|p| p.pss_kb).sum();
 
-        let efficiency = if total_rss > 0 {
-            ((total_rss - total_pss) as f64 / total_rss as f64) * 100.0
-        } else {
-            0.0
-        };
+        let efficiency = sharing_pct(total_rss, total_pss);
 
         SharedMemoryStats {
             total_shared_kb: total_shared,
             total_shared_clean_kb: total_shared_clean,
             total_shared_dirty_kb: total_shared_dirty,
             sharing_efficiency: efficiency,
+            shmem_kb: system.shmem_kb,
+            sysv_shm_kb: shm_segments.iter().map(|s| s.size_kb).sum(),
+            shm_segments,
         }
     }
 
-    fn build_memory_map(&self, system: &SystemMemory, processes: &[ProcessMemory]) -> MemoryMap {
+    fn build_memory_map(
+        &self,
+        system: &SystemMemory,
+        processes: &[ProcessMemory],
+        complete: bool,
+    ) -> MemoryMap {
         let total_private: u64 = processes
             .iter()
             .map(|p| p.private_clean_kb + p.private_dirty_kb)
@@ -173,9 +688,22 @@ impl Analyzer {
             .map(|p| p.shared_clean_kb + p.shared_dirty_kb)
             .sum();
 
-        let accounted = total_private + system.cached_kb + system.buffers_kb + system.free_kb + system.slab_kb + system.page_tables_kb;
+        // The hugepage pool is carved out of MemTotal up front and never shows
+        // up in MemFree or Cached, so it has to be accounted for separately.
+        let accounted = total_private + system.cached_kb + system.buffers_kb + system.free_kb + system.slab_kb + system.page_tables_kb + system.hugepages_total_kb;
         let kernel = system.total_kb.saturating_sub(accounted);
 
+        // Two independent counts of the same pages: every resident page of a
+        // process mapping is either anonymous or mapped file/shmem, and PSS
+        // counts each of them exactly once across processes. They can only be
+        // compared when every process was read with its PSS, and not on
+        // kernels or recordings without AnonPages.
+        let process_pss: u64 = processes.iter().map(|p| p.pss_kb).sum();
+        let anon_mapped = system.anon_pages_kb + system.mapped_kb;
+        let comparable = complete && system.anon_pages_kb > 0 && processes.iter().all(|p| !p.rss_only);
+        let reconciliation_error =
+            comparable.then(|| process_pss as i64 - anon_mapped as i64);
+
         MemoryMap {
             kernel_kb: kernel,
             process_private_kb: total_private,
@@ -185,40 +713,244 @@ impl Analyzer {
             free_kb: system.free_kb,
             slab_kb: system.slab_kb,
             page_tables_kb: system.page_tables_kb,
+            hugepages_used_kb: system.hugepages_total_kb.saturating_sub(system.hugepages_free_kb),
+            hugepages_free_kb: system.hugepages_free_kb,
+            anon_hugepages_kb: system.anon_hugepages_kb,
+            dirty_kb: system.dirty_kb,
+            writeback_kb: system.writeback_kb,
+            process_pss_kb: process_pss,
+            anon_mapped_kb: anon_mapped,
+            reconciliation_error_kb: reconciliation_error,
         }
     }
+}
 
-    fn empty_state(&self) -> AnalyzedState {
-        AnalyzedState {
-            processes: Vec::new(),
-            system: SystemStats {
-                total_kb: 0,
-                used_kb: 0,
-                available_kb: 0,
-                cached_kb: 0,
-                buffers_kb: 0,
-                swap_total_kb: 0,
-                swap_used_kb: 0,
-                total_process_pss_kb: 0,
-                total_process_rss_kb: 0,
-            },
-            shared_memory: SharedMemoryStats {
-                total_shared_kb: 0,
-                total_shared_clean_kb: 0,
-                total_shared_dirty_kb: 0,
-                sharing_efficiency: 0.0,
-            },
-            numa_nodes: Vec::new(),
-            memory_map: MemoryMap {
-                kernel_kb: 0,
-                process_private_kb: 0,
-                process_shared_kb: 0,
-                cache_kb: 0,
-                buffers_kb: 0,
-                free_kb: 0,
-                slab_kb: 0,
-                page_tables_kb: 0,
-            },
+// Running PSS aggregates for one PID over the session; the sum is kept rather
+// than a history so a long watch costs the same as a short one.
+#[derive(Debug, Clone, Copy)]
+struct PssAggregate {
+    min_kb: u64,
+    max_kb: u64,
+    sum_kb: u128,
+    samples: u64,
+}
+
+impl PssAggregate {
+    fn new(pss_kb: u64) -> Self {
+        Self {
+            min_kb: pss_kb,
+            max_kb: pss_kb,
+            sum_kb: pss_kb as u128,
+            samples: 1,
+        }
+    }
+
+    fn add(self, pss_kb: u64) -> Self {
+        Self {
+            min_kb: self.min_kb.min(pss_kb),
+            max_kb: self.max_kb.max(pss_kb),
+            sum_kb: self.sum_kb + pss_kb as u128,
+            samples: self.samples + 1,
+        }
+    }
+
+    fn avg_kb(&self) -> u64 {
+        (self.sum_kb / self.samples.max(1) as u128) as u64
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Change {
+    Grew,
+    Shrank,
+    Appeared,
+    Disappeared,
+}
+
+// One row of the process tree, in display order: a parent comes right before
+// its children, which are ordered by subtree PSS, largest first.
+#[derive(Debug, Clone)]
+pub struct TreeRow {
+    pub pid: u32,
+    pub depth: usize,
+    pub subtree_pss_kb: u64,
+    pub descendants: usize,
+}
+
+// Processes whose parent isn't in the list (PID 1, kthreadd, or a parent
+// outside --pid or unreadable) are roots. A PID reused between reading two
+// stat files can form a cycle; its first member reached is made a root.
+fn build_tree(processes: &[ProcessStats]) -> Vec<TreeRow> {
+    let pss: HashMap<u32, u64> = processes.iter().map(|p| (p.pid, p.pss_kb)).collect();
+
+    let mut children: HashMap<u32, Vec<u32>> = HashMap::new();
+    let mut roots = Vec::new();
+    for proc in processes {
+        if proc.ppid != proc.pid && pss.contains_key(&proc.ppid) {
+            children.entry(proc.ppid).or_default().push(proc.pid);
+        } else {
+            roots.push(proc.pid);
+        }
+    }
+
+    // Walk from the roots, then from whatever a cycle left unreached,
+    // keeping only the edges actually followed.
+    let root_set: HashSet<u32> = roots.iter().copied().collect();
+    let mut seen = HashSet::new();
+    let mut order = Vec::with_capacity(processes.len());
+    let mut tree: HashMap<u32, Vec<u32>> = HashMap::new();
+    let starts: Vec<u32> = roots.iter().copied().chain(processes.iter().map(|p| p.pid)).collect();
+    for start in starts {
+        if !seen.insert(start) {
+            continue;
+        }
+        if !root_set.contains(&start) {
+            roots.push(start);
+        }
+        let mut pending = vec![start];
+        while let Some(pid) = pending.pop() {
+            order.push(pid);
+            for &child in children.get(&pid).into_iter().flatten() {
+                if seen.insert(child) {
+                    tree.entry(pid).or_default().push(child);
+                    pending.push(child);
+                }
+            }
+        }
+    }
+
+    // Children come after their parent in the walk, so summing it backwards
+    // finishes every subtree before its root.
+    let mut totals: HashMap<u32, (u64, usize)> = HashMap::new();
+    for &pid in order.iter().rev() {
+        let (kb, count) = tree
+            .get(&pid)
+            .into_iter()
+            .flatten()
+            .fold((pss[&pid], 0), |(kb, count), child| {
+                let (child_kb, child_count) = totals[child];
+                (kb + child_kb, count + child_count + 1)
+            });
+        totals.insert(pid, (kb, count));
+    }
+
+    let by_size = |pids: &mut Vec<u32>| {
+        pids.sort_by_key(|pid| (std::cmp::Reverse(totals[pid].0), *pid));
+    };
+    by_size(&mut roots);
+    for kids in tree.values_mut() {
+        by_size(kids);
+    }
+
+    let mut rows = Vec::with_capacity(order.len());
+    let mut pending: Vec<(u32, usize)> = roots.iter().rev().map(|&pid| (pid, 0)).collect();
+    while let Some((pid, depth)) = pending.pop() {
+        let (subtree_pss_kb, descendants) = totals[&pid];
+        rows.push(TreeRow { pid, depth, subtree_pss_kb, descendants });
+        for &child in tree.get(&pid).into_iter().flatten().rev() {
+            pending.push((child, depth + 1));
+        }
+    }
+    rows
+}
+
+#[derive(Debug, Clone)]
+pub struct ProcessDiff {
+    pub pid: u32,
+    pub name: String,
+    pub change: Change,
+    pub pss_a_kb: Option<u64>,
+    pub pss_b_kb: Option<u64>,
+    pub delta_kb: i64,
+}
+
+// The share of RSS that is shared with other processes: 0 when every page
+// is private, near 100 for a forked worker that touched almost nothing.
+pub(crate) fn sharing_pct(rss_kb: u64, pss_kb: u64) -> f64 {
+    if rss_kb > 0 {
+        rss_kb.saturating_sub(pss_kb) as f64 / rss_kb as f64 * 100.0
+    } else {
+        0.0
+    }
+}
+
+// Keyed by PID and start time so a reused PID shows up as one process leaving
+// and another appearing. Unchanged processes are left out; the largest changes
+// come first.
+pub fn diff_processes(a: &[ProcessStats], b: &[ProcessStats]) -> Vec<ProcessDiff> {
+    let mut before: HashMap<ProcessKey, (&str, u64)> = a
+        .iter()
+        .map(|p| ((p.pid, p.start_time_ticks), (p.name.as_str(), p.pss_kb)))
+        .collect();
+    let mut diff = Vec::new();
+
+    for proc in b {
+        let pss_a = before.remove(&(proc.pid, proc.start_time_ticks)).map(|(_, pss)| pss);
+        let delta = proc.pss_kb as i64 - pss_a.unwrap_or(0) as i64;
+        let change = match pss_a {
+            None => Change::Appeared,
+            Some(_) if delta > 0 => Change::Grew,
+            Some(_) if delta < 0 => Change::Shrank,
+            Some(_) => continue,
+        };
+
+        diff.push(ProcessDiff {
+            pid: proc.pid,
+            name: proc.name.clone(),
+            change,
+            pss_a_kb: pss_a,
+            pss_b_kb: Some(proc.pss_kb),
+            delta_kb: delta,
+        });
+    }
+
+    diff.extend(before.into_iter().map(|((pid, _), (name, pss))| ProcessDiff {
+        pid,
+        name: name.to_string(),
+        change: Change::Disappeared,
+        pss_a_kb: Some(pss),
+        pss_b_kb: None,
+        delta_kb: -(pss as i64),
+    }));
+
+    diff.sort_by_key(|d| (std::cmp::Reverse(d.delta_kb.unsigned_abs()), d.pid));
+    diff
+}
+
+// Returns the retained samples oldest first, for charting.
+fn push_capped<T: Copy>(history: &mut VecDeque<T>, value: T) -> Vec<T> {
+    if history.len() == SYSTEM_HISTORY_LEN {
+        history.pop_front();
+    }
+    history.push_back(value);
+
+    history.iter().copied().collect()
+}
+
+// Resident pages are either anonymous or file/shmem backed; swapped-out pages
+// are not part of Rss, so nothing needs subtracting for them.
+fn file_backed_kb(proc: &ProcessMemory) -> u64 {
+    proc.rss_kb.saturating_sub(proc.anonymous_kb)
+}
+
+// Flags a process whose PSS never dropped across the last `window` samples and
+// grew on at least half of the steps, which filters out one-off bumps.
+fn is_monotonic_growth(samples: &VecDeque<u64>, window: usize) -> bool {
+    if samples.len() < window {
+        return false;
+    }
+
+    let recent: Vec<u64> = samples.iter().skip(samples.len() - window).copied().collect();
+    let mut increases = 0;
+
+    for pair in recent.windows(2) {
+        if pair[1] < pair[0] {
+            return false;
+        }
+        if pair[1] > pair[0] {
+            increases += 1;
         }
     }
-}
\ No newline at end of file
+
+    increases * 2 >= window - 1
+}
@@ -0,0 +1,320 @@
+use crate::{
+    analyzer::{MAX_HISTORY_LEN, MIN_HISTORY_LEN},
+    columns::Column,
+    config,
+    theme::Palette,
+    ui::{SortMode, ViewMode},
+    units::Unit,
+};
+use anyhow::{Context, Result, anyhow};
+use std::path::PathBuf;
+use std::time::Duration;
+
+const DEFAULT_INTERVAL: Duration = Duration::from_millis(1000);
+
+const USAGE: &str = "Usage: memz [OPTIONS]
+
+Options:
+  --json                 Print one analyzed snapshot as JSON and exit
+  --stream               Print one JSON object per tick (newline-delimited) forever
+  --interval <SECS>      Refresh interval in seconds, fractions allowed [default: 1]
+  --history <N>          PSS samples kept per process for sparklines and leak detection [default: 60]
+  --no-root              Run without root, skipping processes whose memory maps are unreadable
+  --wx-scan              Read full smaps to find writable+executable mappings (slower)
+  --numa-scan            Read numa_maps to attribute process memory to NUMA nodes (slower)
+  --fast                 Read only RSS and swap from /proc/<pid>/status, skipping PSS (much faster)
+  --pid <PID>            Only monitor this process and its descendants
+  --threads <N>          Worker threads for reading /proc [default: CPU count]
+  --metrics-port <PORT>  Serve Prometheus metrics on http://0.0.0.0:<PORT>/metrics
+  --no-tui               Run headless (requires --metrics-port)
+  --check                Collect once, print a status line and exit 2 if memory use is over --threshold-pct
+  --threshold-pct <PCT>  Memory use percentage that fails --check [default: 90]
+  --once                 Draw one TUI frame from a single collection and exit on any key
+  --dump-frame           Render one TUI frame at 120x40 and print it as plain text
+  --summary              On exit, print session duration, peak usage and top processes as JSON
+  --top <N>              Limit --json/--stream to the first N processes by --sort (0: all)
+  --max-failures <N>     Exit after N consecutive failed refreshes [default: 10]
+  --proc-root <DIR>      Read DIR instead of /proc (and its sibling sys/ instead of /sys)
+  --record <FILE>        Append every collected snapshot to FILE as JSON lines
+  --replay <FILE>        Replay snapshots from a --record file instead of reading /proc
+  --watch-pid <PID>      Log PID's PSS, RSS and swap to --watch-log every refresh
+  --watch-log <FILE>     Append --watch-pid lines to FILE
+  --watch-follow         When the watched process exits, wait for one with the same name
+  --theme <NAME>         Color theme: default, light, mono [default: default]
+  --alert-pss <MB>       Ring the bell and highlight processes whose PSS exceeds MB
+  --on-alert <COMMAND>   Run COMMAND with the PID and name of each process crossing --alert-pss
+  --confirm-quit         Ask for confirmation before q exits the TUI
+  --bold-delta <KB>      Bold rows whose PSS changed by more than KB in one tick [default: 10240]
+  --delta-kb             Show the Delta column in exact kB regardless of --units
+  --debug                Report /proc parse anomalies (D in the TUI, stderr otherwise)
+  --units <UNIT>         Memory units: auto, mib, gib, pages [default: auto]
+  --sort <MODE>          Initial sort: pss, rss, shared, swap, private_dirty, pid, oom,
+                         threads, growth, age, thp [default: pss]
+  --columns <LIST>       Comma-separated process columns to show, in order
+                         (pid,user,name,pss,mem_pct,sharing,rss,shared,
+                         shared_clean,shared_dirty,private,private_clean,
+                         private_dirty,swap,oom,threads,delta,growth,age,thp)
+  -h, --help             Show this help";
+
+#[derive(Debug, Clone)]
+pub struct Args {
+    pub json: bool,
+    pub stream: bool,
+    pub interval: Duration,
+    pub pid: Option<u32>,
+    pub threads: Option<usize>,
+    pub history: Option<usize>,
+    pub max_failures: Option<u32>,
+    pub top: usize,
+    pub summary: bool,
+    pub once: bool,
+    pub dump_frame: bool,
+    pub check: bool,
+    pub threshold_pct: Option<f64>,
+    pub theme: Palette,
+    pub metrics_port: Option<u16>,
+    pub no_tui: bool,
+    pub no_root: bool,
+    pub wx_scan: bool,
+    pub numa_scan: bool,
+    pub fast: bool,
+    pub sort: Option<SortMode>,
+    pub view: Option<ViewMode>,
+    pub hide_kernel_threads: bool,
+    pub name_filter: String,
+    pub columns: Vec<Column>,
+    pub alert_pss_mb: Option<u64>,
+    pub on_alert: Option<String>,
+    pub unit: Unit,
+    pub confirm_quit: bool,
+    pub debug: bool,
+    pub bold_delta_kb: Option<u64>,
+    pub delta_kb: bool,
+    pub proc_root: Option<PathBuf>,
+    pub record: Option<PathBuf>,
+    pub replay: Option<PathBuf>,
+    pub watch_pid: Option<u32>,
+    pub watch_log: Option<PathBuf>,
+    pub watch_follow: bool,
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Self {
+            json: false,
+            stream: false,
+            interval: DEFAULT_INTERVAL,
+            pid: None,
+            threads: None,
+            history: None,
+            max_failures: None,
+            top: 0,
+            summary: false,
+            once: false,
+            dump_frame: false,
+            check: false,
+            threshold_pct: None,
+            theme: Palette::default(),
+            metrics_port: None,
+            no_tui: false,
+            no_root: false,
+            wx_scan: false,
+            numa_scan: false,
+            fast: false,
+            sort: None,
+            view: None,
+            hide_kernel_threads: false,
+            name_filter: String::new(),
+            columns: Column::DEFAULT.to_vec(),
+            alert_pss_mb: None,
+            on_alert: None,
+            unit: Unit::Auto,
+            confirm_quit: false,
+            debug: false,
+            bold_delta_kb: None,
+            delta_kb: false,
+            proc_root: None,
+            record: None,
+            replay: None,
+            watch_pid: None,
+            watch_log: None,
+            watch_follow: false,
+        }
+    }
+}
+
+impl Args {
+    pub fn parse() -> Result<Self> {
+        let defaults = config::load_args()?;
+        Self::parse_from(std::env::args().skip(1), defaults)
+    }
+
+    fn parse_from<I: Iterator<Item = String>>(mut iter: I, mut args: Args) -> Result<Self> {
+
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "--json" => args.json = true,
+                "--stream" => args.stream = true,
+                "--interval" => args.interval = parse_interval(&value(&mut iter, &arg)?)?,
+                "--metrics-port" => {
+                    args.metrics_port = Some(parse_number(&value(&mut iter, &arg)?, &arg)?)
+                }
+                "--no-tui" => args.no_tui = true,
+                "--summary" => args.summary = true,
+                "--once" => args.once = true,
+                "--dump-frame" => args.dump_frame = true,
+                "--check" => args.check = true,
+                "--threshold-pct" => {
+                    args.threshold_pct = Some(parse_number(&value(&mut iter, &arg)?, &arg)?)
+                }
+                "--no-root" => args.no_root = true,
+                "--wx-scan" => args.wx_scan = true,
+                "--numa-scan" => args.numa_scan = true,
+                "--fast" => args.fast = true,
+                "--confirm-quit" => args.confirm_quit = true,
+                "--debug" => args.debug = true,
+                "--delta-kb" => args.delta_kb = true,
+                "--bold-delta" => {
+                    args.bold_delta_kb = Some(parse_number(&value(&mut iter, &arg)?, &arg)?)
+                }
+                "--proc-root" => args.proc_root = Some(PathBuf::from(value(&mut iter, &arg)?)),
+                "--record" => args.record = Some(PathBuf::from(value(&mut iter, &arg)?)),
+                "--replay" => args.replay = Some(PathBuf::from(value(&mut iter, &arg)?)),
+                "--watch-pid" => {
+                    args.watch_pid = Some(parse_number(&value(&mut iter, &arg)?, &arg)?)
+                }
+                "--watch-log" => args.watch_log = Some(PathBuf::from(value(&mut iter, &arg)?)),
+                "--watch-follow" => args.watch_follow = true,
+                "--theme" => args.theme = Palette::by_name(&value(&mut iter, &arg)?)?,
+                "--units" => args.unit = Unit::by_name(&value(&mut iter, &arg)?)?,
+                "--sort" => args.sort = Some(SortMode::by_name(&value(&mut iter, &arg)?)?),
+                "--columns" => args.columns = Column::parse_list(&value(&mut iter, &arg)?)?,
+                "--alert-pss" => {
+                    args.alert_pss_mb = Some(parse_number(&value(&mut iter, &arg)?, &arg)?)
+                }
+                "--on-alert" => args.on_alert = Some(value(&mut iter, &arg)?),
+                "--pid" => args.pid = Some(parse_number(&value(&mut iter, &arg)?, &arg)?),
+                "--top" => args.top = parse_number(&value(&mut iter, &arg)?, &arg)?,
+                "--max-failures" => {
+                    args.max_failures = Some(parse_number(&value(&mut iter, &arg)?, &arg)?)
+                }
+                "--history" => args.history = Some(parse_number(&value(&mut iter, &arg)?, &arg)?),
+                "--threads" => args.threads = Some(parse_number(&value(&mut iter, &arg)?, &arg)?),
+                "-h" | "--help" => {
+                    println!("{}", USAGE);
+                    std::process::exit(0);
+                }
+                other => return Err(anyhow!("Unknown argument: {}\n\n{}", other, USAGE)),
+            }
+        }
+
+        if args.json && args.stream {
+            return Err(anyhow!("--json and --stream cannot be combined"));
+        }
+
+        if args.record.is_some() && args.replay.is_some() {
+            return Err(anyhow!("--record and --replay cannot be combined"));
+        }
+
+        if args.proc_root.is_some() && args.replay.is_some() {
+            return Err(anyhow!("--proc-root and --replay cannot be combined"));
+        }
+
+        if args.fast && (args.wx_scan || args.numa_scan) {
+            return Err(anyhow!("--fast cannot be combined with --wx-scan or --numa-scan"));
+        }
+
+        if args.watch_pid.is_some() != args.watch_log.is_some() {
+            return Err(anyhow!("--watch-pid and --watch-log must be given together"));
+        }
+
+        if args.watch_follow && args.watch_pid.is_none() {
+            return Err(anyhow!("--watch-follow requires --watch-pid"));
+        }
+
+        if args.on_alert.is_some() && args.alert_pss_mb.is_none() {
+            return Err(anyhow!("--on-alert requires --alert-pss"));
+        }
+
+        if args.max_failures == Some(0) {
+            return Err(anyhow!("--max-failures must be at least 1"));
+        }
+
+        if args.threads == Some(0) {
+            return Err(anyhow!("--threads must be at least 1"));
+        }
+
+        if let Some(n) = args.history
+            && !(MIN_HISTORY_LEN..=MAX_HISTORY_LEN).contains(&n)
+        {
+            return Err(anyhow!(
+                "--history must be between {} and {}, got {}",
+                MIN_HISTORY_LEN,
+                MAX_HISTORY_LEN,
+                n
+            ));
+        }
+
+        if args.top > 0 && !args.json && !args.stream {
+            return Err(anyhow!("--top requires --json or --stream"));
+        }
+
+        if args.check && (args.json || args.stream || args.no_tui || args.summary) {
+            return Err(anyhow!("--check cannot be combined with --json, --stream, --no-tui or --summary"));
+        }
+
+        if args.threshold_pct.is_some() && !args.check {
+            return Err(anyhow!("--threshold-pct requires --check"));
+        }
+
+        if let Some(pct) = args.threshold_pct
+            && !(pct > 0.0 && pct <= 100.0)
+        {
+            return Err(anyhow!("--threshold-pct must be between 0 and 100, got {}", pct));
+        }
+
+        if (args.once || args.dump_frame) && (args.json || args.stream || args.no_tui || args.check) {
+            return Err(anyhow!("--once and --dump-frame only apply to the TUI"));
+        }
+
+        if args.dump_frame && (args.once || args.summary) {
+            return Err(anyhow!("--dump-frame cannot be combined with --once or --summary"));
+        }
+
+        if args.summary && (args.json || args.stream || args.no_tui) {
+            return Err(anyhow!("--summary only applies to the TUI"));
+        }
+
+        if args.no_tui && args.metrics_port.is_none() {
+            return Err(anyhow!("--no-tui requires --metrics-port"));
+        }
+
+        Ok(args)
+    }
+}
+
+fn value<I: Iterator<Item = String>>(iter: &mut I, flag: &str) -> Result<String> {
+    iter.next()
+        .ok_or_else(|| anyhow!("Missing value for {}\n\n{}", flag, USAGE))
+}
+
+fn parse_interval(raw: &str) -> Result<Duration> {
+    let secs = raw
+        .parse::<f64>()
+        .with_context(|| format!("Invalid interval: {}", raw))?;
+
+    interval_from_secs(secs)
+}
+
+pub(crate) fn interval_from_secs(secs: f64) -> Result<Duration> {
+    if !secs.is_finite() || secs < 0.1 {
+        return Err(anyhow!("Interval must be at least 0.1 seconds, got {}", secs));
+    }
+
+    Duration::try_from_secs_f64(secs).map_err(|err| anyhow!("Invalid interval {}: {}", secs, err))
+}
+
+fn parse_number<T: std::str::FromStr>(raw: &str, flag: &str) -> Result<T> {
+    raw.parse::<T>()
+        .map_err(|_| anyhow!("Invalid value for {}: {}", flag, raw))
+}
@@ -0,0 +1,26 @@
+use anyhow::Result;
+
+// On X11 the copied text only stays available while its owner is alive, so
+// the clipboard handle is opened on first use and kept for the session.
+#[derive(Default)]
+pub struct Clipboard {
+    #[cfg(feature = "clipboard")]
+    inner: Option<arboard::Clipboard>,
+}
+
+impl Clipboard {
+    #[cfg(feature = "clipboard")]
+    pub fn copy(&mut self, text: &str) -> Result<()> {
+        let inner = match &mut self.inner {
+            Some(inner) => inner,
+            None => self.inner.insert(arboard::Clipboard::new()?),
+        };
+        inner.set_text(text)?;
+        Ok(())
+    }
+
+    #[cfg(not(feature = "clipboard"))]
+    pub fn copy(&mut self, _text: &str) -> Result<()> {
+        anyhow::bail!("built without clipboard support")
+    }
+}
@@ -1,19 +1,38 @@
+use crate::os_utils;
 use anyhow::{Context, Result};
-use std::collections::HashSet;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
 use std::fs;
-use std::path::PathBuf;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::thread;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MemorySnapshot {
     pub processes: Vec<ProcessMemory>,
     pub system: SystemMemory,
     pub numa_nodes: Vec<NumaNode>,
+    pub pressure: Option<MemoryPressure>,
+    pub processes_skipped: usize,
+    #[serde(default)]
+    pub processes_unreadable: usize,
+    #[serde(default)]
+    pub shm_segments: Vec<ShmSegment>,
+    #[serde(default)]
+    pub vmstat: Option<Vmstat>,
+    #[serde(default)]
+    pub uptime_secs: f64,
+    // Set with --pid, when only part of the process list was collected.
+    #[serde(default)]
+    pub pid_filtered: bool,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ProcessMemory {
     pub pid: u32,
     pub name: String,
+    pub cmdline: String,
     pub rss_kb: u64,
     pub pss_kb: u64,
     pub shared_clean_kb: u64,
@@ -21,9 +40,40 @@ pub struct ProcessMemory {
     pub private_clean_kb: u64,
     pub private_dirty_kb: u64,
     pub swap_kb: u64,
+    pub swap_pss_kb: u64,
+    #[serde(default)]
+    pub anonymous_kb: u64,
+    #[serde(default)]
+    pub anon_huge_kb: u64,
+    pub oom_score: i32,
+    pub oom_score_adj: i32,
+    pub cgroup: String,
+    pub uid: u32,
+    pub username: String,
+    pub threads: u32,
+    #[serde(default)]
+    pub is_kernel_thread: bool,
+    #[serde(default)]
+    pub minor_faults: u64,
+    #[serde(default)]
+    pub major_faults: u64,
+    #[serde(default)]
+    pub wx_regions: u32,
+    #[serde(default)]
+    pub wx_kb: u64,
+    #[serde(default)]
+    pub numa_kb: BTreeMap<u32, u64>,
+    #[serde(default)]
+    pub exe: String,
+    #[serde(default)]
+    pub start_time_ticks: u64,
+    #[serde(default)]
+    pub rss_only: bool,
+    #[serde(default)]
+    pub ppid: u32,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SystemMemory {
     pub total_kb: u64,
     pub free_kb: u64,
@@ -34,9 +84,68 @@ pub struct SystemMemory {
     pub swap_free_kb: u64,
     pub slab_kb: u64,
     pub page_tables_kb: u64,
+    pub hugepages_total_kb: u64,
+    pub hugepages_free_kb: u64,
+    pub anon_hugepages_kb: u64,
+    #[serde(default)]
+    pub shmem_kb: u64,
+    #[serde(default)]
+    pub anon_pages_kb: u64,
+    #[serde(default)]
+    pub mapped_kb: u64,
+    #[serde(default)]
+    pub dirty_kb: u64,
+    #[serde(default)]
+    pub writeback_kb: u64,
+    #[serde(default)]
+    pub commit_limit_kb: u64,
+    #[serde(default)]
+    pub committed_as_kb: u64,
+    #[serde(default)]
+    pub overcommit_memory: Option<u32>,
+    #[serde(default)]
+    pub cgroup_limit: Option<CgroupMemory>,
+    #[serde(default)]
+    pub zswap: Option<Zswap>,
 }
 
-#[derive(Debug, Clone)]
+// Zswap is the compressed pool size, Zswapped the uncompressed size of the
+// pages stored in it. Both only exist on kernels built with zswap (5.19+).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Zswap {
+    pub pool_kb: u64,
+    pub stored_kb: u64,
+}
+
+// Cumulative event counters since boot from /proc/vmstat, in pages.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Vmstat {
+    pub pswpin: u64,
+    pub pswpout: u64,
+    pub pgmajfault: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CgroupMemory {
+    pub limit_kb: u64,
+    pub usage_kb: u64,
+    pub inactive_file_kb: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShmSegment {
+    pub key: i32,
+    pub shmid: u32,
+    pub size_kb: u64,
+    pub rss_kb: u64,
+    pub swap_kb: u64,
+    pub attached: u32,
+    pub creator_pid: u32,
+    pub uid: u32,
+    pub owner: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NumaNode {
     pub node_id: u32,
     pub mem_total_kb: u64,
@@ -44,9 +153,40 @@ pub struct NumaNode {
     pub mem_used_kb: u64,
 }
 
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MemoryPressure {
+    pub some_avg10: f64,
+    pub some_avg60: f64,
+    pub some_avg300: f64,
+    pub full_avg10: f64,
+    pub full_avg60: f64,
+    pub full_avg300: f64,
+}
+
+#[derive(Debug)]
+pub struct RootProcessExited(pub u32);
+
+impl fmt::Display for RootProcessExited {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Process {} exited, nothing left to monitor", self.0)
+    }
+}
+
+impl std::error::Error for RootProcessExited {}
+
 pub struct Collector {
     known_pids: HashSet<u32>,
     proc_path: PathBuf,
+    sys_path: PathBuf,
+    root_pid: Option<u32>,
+    usernames: HashMap<u32, String>,
+    threads: usize,
+    scratch: Vec<String>,
+    debug: bool,
+    warnings: Vec<String>,
+    wx_scan: bool,
+    numa_scan: bool,
+    fast: bool,
 }
 
 impl Collector {
@@ -54,65 +194,237 @@ impl Collector {
         Ok(Self {
             known_pids: HashSet::new(),
             proc_path: PathBuf::from("/proc"),
+            sys_path: PathBuf::from("/sys"),
+            root_pid: None,
+            usernames: HashMap::new(),
+            threads: thread::available_parallelism().map_or(1, |n| n.get()),
+            scratch: Vec::new(),
+            debug: false,
+            warnings: Vec::new(),
+            wx_scan: false,
+            numa_scan: false,
+            fast: false,
         })
     }
 
+    // A captured tree is expected to keep sys/ next to proc/, as on a live
+    // system, so /sys reads follow the proc root to its sibling.
+    pub fn with_proc_root(mut self, proc_root: Option<PathBuf>) -> Self {
+        if let Some(root) = proc_root {
+            self.sys_path = root.parent().unwrap_or(Path::new("/")).join("sys");
+            self.proc_path = root;
+        }
+        self
+    }
+
+    pub fn with_threads(mut self, threads: Option<usize>) -> Self {
+        if let Some(threads) = threads {
+            self.threads = threads.max(1);
+        }
+        self
+    }
+
+    // Reads the full smaps of every process, which is much slower than
+    // smaps_rollup on processes with many mappings.
+    pub fn with_wx_scan(mut self, wx_scan: bool) -> Self {
+        self.wx_scan = wx_scan;
+        self
+    }
+
+    // numa_maps lists every mapping too, so this costs about as much.
+    pub fn with_numa_scan(mut self, numa_scan: bool) -> Self {
+        self.numa_scan = numa_scan;
+        self
+    }
+
+    // Reads /proc/[pid]/status instead of smaps_rollup: RSS and swap only,
+    // but one small file the kernel doesn't have to walk page tables for.
+    pub fn with_fast(mut self, fast: bool) -> Self {
+        self.fast = fast;
+        self
+    }
+
+    // Kernels before 4.14 have no smaps_rollup at all, in which case every
+    // process would take the status fallback anyway. A captured tree without
+    // a self entry can't tell, so it counts as supported.
+    pub fn smaps_rollup_supported(&self) -> bool {
+        let own = self.proc_path.join("self");
+        !own.exists() || own.join("smaps_rollup").exists()
+    }
+
+    pub fn with_debug(mut self, debug: bool) -> Self {
+        self.debug = debug;
+        self
+    }
+
+    pub fn take_warnings(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    pub fn with_root_pid(mut self, root_pid: Option<u32>) -> Self {
+        self.root_pid = root_pid;
+        self
+    }
+
     pub fn collect(&mut self) -> Result<MemorySnapshot> {
         let system = self.collect_system_memory()?;
         let numa_nodes = self.collect_numa_info()?;
-        let processes = self.collect_process_memory()?;
+        let (processes, processes_skipped, processes_unreadable) = self.collect_process_memory()?;
+        let pressure = self.collect_memory_pressure();
+        let shm_segments = self.collect_shm_segments();
+        let vmstat = self.collect_vmstat();
+        let uptime_secs = self.read_uptime();
 
         Ok(MemorySnapshot {
             processes,
             system,
             numa_nodes,
+            pressure,
+            processes_skipped,
+            processes_unreadable,
+            shm_segments,
+            vmstat,
+            uptime_secs,
+            pid_filtered: self.root_pid.is_some(),
         })
     }
 
-    fn collect_system_memory(&self) -> Result<SystemMemory> {
-        let content = fs::read_to_string("/proc/meminfo")
-            .context("Failed to read /proc/meminfo")?;
-
-        let mut mem = SystemMemory {
-            total_kb: 0,
-            free_kb: 0,
-            available_kb: 0,
-            buffers_kb: 0,
-            cached_kb: 0,
-            swap_total_kb: 0,
-            swap_free_kb: 0,
-            slab_kb: 0,
-            page_tables_kb: 0,
+    // /proc/sysvipc/shm is missing when the kernel lacks SysV IPC, which just
+    // means there are no segments to show.
+    fn collect_shm_segments(&mut self) -> Vec<ShmSegment> {
+        let Ok(content) = fs::read_to_string(self.proc_path.join("sysvipc/shm")) else {
+            return Vec::new();
         };
 
+        let mut segments: Vec<ShmSegment> = content.lines().skip(1).filter_map(parse_shm_line).collect();
+
+        for segment in &mut segments {
+            segment.owner = self.resolve_username(segment.uid);
+        }
+
+        segments.sort_by_key(|s| std::cmp::Reverse(s.size_kb));
+        segments
+    }
+
+    fn collect_memory_pressure(&self) -> Option<MemoryPressure> {
+        let content = fs::read_to_string(self.proc_path.join("pressure/memory")).ok()?;
+        let mut pressure = MemoryPressure::default();
+
         for line in content.lines() {
-            let parts: Vec<&str> = line.split_whitespace().collect();
-            if parts.len() < 2 {
-                continue;
+            let mut parts = line.split_whitespace();
+            let kind = parts.next();
+
+            for field in parts {
+                let Some((key, value)) = field.split_once('=') else {
+                    continue;
+                };
+                let value = value.parse::<f64>().unwrap_or(0.0);
+
+                match (kind, key) {
+                    (Some("some"), "avg10") => pressure.some_avg10 = value,
+                    (Some("some"), "avg60") => pressure.some_avg60 = value,
+                    (Some("some"), "avg300") => pressure.some_avg300 = value,
+                    (Some("full"), "avg10") => pressure.full_avg10 = value,
+                    (Some("full"), "avg60") => pressure.full_avg60 = value,
+                    (Some("full"), "avg300") => pressure.full_avg300 = value,
+                    _ => {}
+                }
             }
+        }
 
-            let value = parts[1].parse::<u64>().unwrap_or(0);
-
-            match parts[0] {
-                "MemTotal:" => mem.total_kb = value,
-                "MemFree:" => mem.free_kb = value,
-                "MemAvailable:" => mem.available_kb = value,
-                "Buffers:" => mem.buffers_kb = value,
-                "Cached:" => mem.cached_kb = value,
-                "SwapTotal:" => mem.swap_total_kb = value,
-                "SwapFree:" => mem.swap_free_kb = value,
-                "Slab:" => mem.slab_kb = value,
-                "PageTables:" => mem.page_tables_kb = value,
+        Some(pressure)
+    }
+
+    // Seconds since boot, the reference process start times are measured from.
+    fn read_uptime(&self) -> f64 {
+        fs::read_to_string(self.proc_path.join("uptime"))
+            .ok()
+            .and_then(|s| s.split_whitespace().next()?.parse().ok())
+            .unwrap_or(0.0)
+    }
+
+    fn collect_vmstat(&self) -> Option<Vmstat> {
+        let content = fs::read_to_string(self.proc_path.join("vmstat")).ok()?;
+        let mut vmstat = Vmstat::default();
+
+        for line in content.lines() {
+            let Some((key, value)) = line.split_once(' ') else {
+                continue;
+            };
+            let value = value.trim().parse::<u64>().unwrap_or(0);
+
+            match key {
+                "pswpin" => vmstat.pswpin = value,
+                "pswpout" => vmstat.pswpout = value,
+                "pgmajfault" => vmstat.pgmajfault = value,
                 _ => {}
             }
         }
 
+        Some(vmstat)
+    }
+
+    fn collect_system_memory(&mut self) -> Result<SystemMemory> {
+        let content = fs::read_to_string(self.proc_path.join("meminfo"))
+            .context("Failed to read /proc/meminfo")?;
+
+        let (mut mem, warnings) = parse_meminfo(&content);
+        if self.debug {
+            self.warnings.extend(warnings);
+        }
+
+        mem.cgroup_limit = self
+            .collect_cgroup_memory()
+            .filter(|cg| cg.limit_kb < mem.total_kb);
+        mem.overcommit_memory = fs::read_to_string(self.proc_path.join("sys/vm/overcommit_memory"))
+            .ok()
+            .and_then(|s| s.trim().parse().ok());
+
         Ok(mem)
     }
 
+    // The limit of memz's own cgroup, so a container reports its own budget
+    // rather than the host's. Without a cgroup namespace /proc/self/cgroup
+    // names a host path that isn't mounted inside the container, so the
+    // mount root is tried as well.
+    fn collect_cgroup_memory(&self) -> Option<CgroupMemory> {
+        let content = fs::read_to_string(self.proc_path.join("self/cgroup")).ok()?;
+
+        for line in content.lines() {
+            let mut fields = line.splitn(3, ':');
+            let (_, controllers, path) = (fields.next()?, fields.next()?, fields.next()?);
+            let path = path.trim_start_matches('/');
+            let cgroup_path = self.sys_path.join("fs/cgroup");
+
+            if controllers.is_empty() {
+                let dirs = [cgroup_path.join(path), cgroup_path];
+                if let Some(cg) = dirs.iter().find_map(|dir| {
+                    read_cgroup_memory(dir, "memory.max", "memory.current", "inactive_file")
+                }) {
+                    return Some(cg);
+                }
+            } else if controllers.split(',').any(|c| c == "memory") {
+                let root = cgroup_path.join("memory");
+                let dirs = [root.join(path), root];
+                if let Some(cg) = dirs.iter().find_map(|dir| {
+                    read_cgroup_memory(
+                        dir,
+                        "memory.limit_in_bytes",
+                        "memory.usage_in_bytes",
+                        "total_inactive_file",
+                    )
+                }) {
+                    return Some(cg);
+                }
+            }
+        }
+
+        None
+    }
+
     fn collect_numa_info(&self) -> Result<Vec<NumaNode>> {
         let mut nodes = Vec::new();
-        let sys_node_path = PathBuf::from("/sys/devices/system/node");
+        let sys_node_path = self.sys_path.join("devices/system/node");
 
         if !sys_node_path.exists() {
             return Ok(nodes);
@@ -168,39 +480,255 @@ impl Collector {
         Ok(nodes)
     }
 
-    fn collect_process_memory(&mut self) -> Result<Vec<ProcessMemory>> {
-        let mut processes = Vec::new();
-        let mut current_pids = HashSet::new();
+    // A PID is kept if its smaps_rollup parsed or, failing that (or with
+    // --fast), its status gave an RSS or showed a kernel thread. Of the rest,
+    // one whose /proc entry vanished between read_dir and the read exited and
+    // is counted as skipped; one denied to us is counted as unreadable, as is
+    // a denied PID kept with RSS only.
+    fn collect_process_memory(&mut self) -> Result<(Vec<ProcessMemory>, usize, usize)> {
+        let mut current_pids = self.list_pids()?;
+
+        if let Some(root) = self.root_pid {
+            current_pids = self.descendants_of(root, &current_pids)?;
+        }
+
+        let pids: Vec<u32> = current_pids.into_iter().collect();
+        let chunk_len = pids.len().div_ceil(self.threads).max(1);
+
+        // Each worker reads its share of PIDs into its own reused buffer; the
+        // buffers are taken out of self so the workers can borrow it shared.
+        let mut scratch = std::mem::take(&mut self.scratch);
+        scratch.resize_with(pids.len().div_ceil(chunk_len), String::new);
+
+        let this = &*self;
+        let results: Vec<(Vec<ProcessMemory>, usize, usize)> = thread::scope(|scope| {
+            let workers: Vec<_> = pids
+                .chunks(chunk_len)
+                .zip(scratch.iter_mut())
+                .map(|(chunk, buf)| scope.spawn(move || this.read_processes(chunk, buf)))
+                .collect();
+
+            workers
+                .into_iter()
+                .map(|worker| worker.join().expect("collector worker panicked"))
+                .collect()
+        });
+
+        self.scratch = scratch;
+
+        let mut processes = Vec::with_capacity(pids.len());
+        let mut skipped = 0;
+        let mut unreadable = 0;
+        for (chunk, chunk_skipped, chunk_unreadable) in results {
+            processes.extend(chunk);
+            skipped += chunk_skipped;
+            unreadable += chunk_unreadable;
+        }
+
+        for proc in &mut processes {
+            proc.username = self.resolve_username(proc.uid);
+        }
+
+        self.known_pids = processes.iter().map(|p| p.pid).collect();
+
+        Ok((processes, skipped, unreadable))
+    }
+
+    // Returns the processes read, the PIDs that exited mid-scan and the PIDs
+    // whose smaps_rollup we lack permission for (only without root), whether
+    // or not status let us keep them with RSS only.
+    fn read_processes(&self, pids: &[u32], buf: &mut String) -> (Vec<ProcessMemory>, usize, usize) {
+        let mut processes = Vec::with_capacity(pids.len());
+        let mut skipped = 0;
+        let mut unreadable = 0;
+
+        for &pid in pids {
+            let smaps_path = self.proc_path.join(pid.to_string()).join("smaps_rollup");
+
+            let mut parsed = if self.fast {
+                self.read_status_only(pid, buf)
+                    .with_context(|| format!("No VmRSS in status for PID {}", pid))
+            } else {
+                self.read_smaps_rollup(pid, &smaps_path, buf)
+            };
+            let denied = parsed.as_ref().is_err_and(|err| {
+                err.downcast_ref::<io::Error>()
+                    .is_some_and(|e| e.kind() == io::ErrorKind::PermissionDenied)
+            });
+            if parsed.is_err()
+                && !self.fast
+                && let Some(proc_mem) = self.read_status_only(pid, buf)
+            {
+                parsed = Ok(proc_mem);
+            }
+
+            if let Ok(mut proc_mem) = parsed {
+                if denied && !proc_mem.is_kernel_thread {
+                    unreadable += 1;
+                }
+                proc_mem.oom_score = self.read_proc_number(pid, "oom_score", buf).unwrap_or(0);
+                proc_mem.oom_score_adj =
+                    self.read_proc_number(pid, "oom_score_adj", buf).unwrap_or(0);
+                proc_mem.cgroup = self.get_process_cgroup(pid, buf);
+                if read_into(&self.proc_path.join(pid.to_string()).join("stat"), buf).is_ok() {
+                    // ppid, minflt, majflt and starttime are fields 4, 10, 12
+                    // and 22 in proc(5).
+                    let fields = stat_fields(buf);
+                    let field = |i: usize| fields.get(i).and_then(|f| f.parse().ok()).unwrap_or(0);
+                    proc_mem.ppid = field(1) as u32;
+                    proc_mem.minor_faults = field(7);
+                    proc_mem.major_faults = field(9);
+                    proc_mem.start_time_ticks = field(19);
+                }
+                if self.read_status(pid, buf).is_ok() {
+                    proc_mem.threads = status_value(buf, "Threads:")
+                        .and_then(|v| v.parse().ok())
+                        .unwrap_or(0);
+                    proc_mem.uid = parse_uid(buf).unwrap_or(0);
+                    // smaps_rollup and VmSwap can disagree; a zero from the
+                    // former is not trusted over a nonzero VmSwap.
+                    if proc_mem.swap_kb == 0
+                        && let Some(swap) = status_kb(buf, "VmSwap:")
+                    {
+                        proc_mem.swap_kb = swap;
+                        proc_mem.swap_pss_kb = proc_mem.swap_pss_kb.max(swap);
+                    }
+                }
+                if self.wx_scan
+                    && read_into(&self.proc_path.join(pid.to_string()).join("smaps"), buf).is_ok()
+                {
+                    (proc_mem.wx_regions, proc_mem.wx_kb) = count_wx_regions(buf);
+                }
+                if self.numa_scan
+                    && read_into(&self.proc_path.join(pid.to_string()).join("numa_maps"), buf)
+                        .is_ok()
+                {
+                    proc_mem.numa_kb = parse_numa_maps(buf);
+                }
+                processes.push(proc_mem);
+            } else if !self.proc_path.join(pid.to_string()).exists() {
+                // Zombies and PIDs we lack permission for fail every tick;
+                // only a vanished /proc entry means the process exited.
+                skipped += 1;
+            } else if denied {
+                unreadable += 1;
+            }
+        }
+
+        (processes, skipped, unreadable)
+    }
 
+    fn list_pids(&self) -> Result<HashSet<u32>> {
         let entries = fs::read_dir(&self.proc_path).context("Failed to read /proc")?;
 
-        for entry in entries.flatten() {
-            let name = entry.file_name();
-            let name_str = name.to_string_lossy();
+        Ok(entries
+            .flatten()
+            .filter_map(|entry| entry.file_name().to_string_lossy().parse::<u32>().ok())
+            .collect())
+    }
+
+    fn descendants_of(&self, root: u32, pids: &HashSet<u32>) -> Result<HashSet<u32>> {
+        if !pids.contains(&root) {
+            return Err(RootProcessExited(root).into());
+        }
 
-            if let Ok(pid) = name_str.parse::<u32>() {
-                current_pids.insert(pid);
+        let mut children: HashMap<u32, Vec<u32>> = HashMap::new();
+        for &pid in pids {
+            if let Some(ppid) = self.read_ppid(pid) {
+                children.entry(ppid).or_default().push(pid);
+            }
+        }
 
-                let smaps_path = entry.path().join("smaps_rollup");
+        let mut tree = HashSet::from([root]);
+        let mut pending = vec![root];
 
-                if let Ok(proc_mem) = self.parse_smaps_rollup(pid, &smaps_path) {
-                    processes.push(proc_mem);
+        while let Some(pid) = pending.pop() {
+            for &child in children.get(&pid).into_iter().flatten() {
+                if tree.insert(child) {
+                    pending.push(child);
                 }
             }
         }
 
-        self.known_pids = current_pids;
+        Ok(tree)
+    }
 
-        Ok(processes)
+    fn read_status(&self, pid: u32, buf: &mut String) -> io::Result<()> {
+        read_into(&self.proc_path.join(pid.to_string()).join("status"), buf)
     }
 
-    fn parse_smaps_rollup(&self, pid: u32, path: &PathBuf) -> Result<ProcessMemory> {
-        let content = fs::read_to_string(path)
+    fn resolve_username(&mut self, uid: u32) -> String {
+        if let Some(name) = self.usernames.get(&uid) {
+            return name.clone();
+        }
+
+        if let Ok(passwd) = fs::read_to_string("/etc/passwd") {
+            for line in passwd.lines() {
+                let fields: Vec<&str> = line.split(':').collect();
+                if let (Some(name), Some(Ok(id))) = (fields.first(), fields.get(2).map(|f| f.parse::<u32>())) {
+                    self.usernames.entry(id).or_insert_with(|| name.to_string());
+                }
+            }
+        }
+
+        self.usernames
+            .entry(uid)
+            .or_insert_with(|| uid.to_string())
+            .clone()
+    }
+
+    fn read_ppid(&self, pid: u32) -> Option<u32> {
+        let stat = fs::read_to_string(self.proc_path.join(pid.to_string()).join("stat")).ok()?;
+        stat_fields(&stat).get(1)?.parse().ok()
+    }
+
+    fn read_smaps_rollup(&self, pid: u32, path: &Path, buf: &mut String) -> Result<ProcessMemory> {
+        read_into(path, buf)
             .with_context(|| format!("Failed to read smaps_rollup for PID {}", pid))?;
 
-        let mut mem = ProcessMemory {
+        let mut mem = self.new_process(pid);
+        parse_smaps_rollup(buf, &mut mem);
+
+        Ok(mem)
+    }
+
+    // status is readable for every process, so without smaps_rollup (no
+    // permission, or a kernel before 4.14) RSS is still known; PSS and the
+    // shared/private split are not. Kernel threads have no smaps_rollup and
+    // no VmRSS either; they own no user memory, so they are kept with zeros.
+    // Zombies have neither and stay skipped.
+    fn read_status_only(&self, pid: u32, buf: &mut String) -> Option<ProcessMemory> {
+        self.read_status(pid, buf).ok()?;
+        let rss_kb = status_kb(buf, "VmRSS:");
+        let anonymous_kb = status_kb(buf, "RssAnon:").unwrap_or(0);
+        let zombie = status_value(buf, "State:").is_some_and(|state| state.starts_with('Z'));
+
+        let mut mem = self.new_process(pid);
+        match rss_kb {
+            Some(rss_kb) => {
+                mem.rss_kb = rss_kb;
+                mem.anonymous_kb = anonymous_kb;
+                mem.rss_only = true;
+            }
+            None if mem.is_kernel_thread && !zombie => {}
+            None => return None,
+        }
+        Some(mem)
+    }
+
+    fn new_process(&self, pid: u32) -> ProcessMemory {
+        let name = self.get_process_name(pid);
+        // Kernel threads are the only processes with an empty cmdline, apart
+        // from zombies, which no longer have an smaps_rollup to read.
+        let cmdline = self.get_process_cmdline(pid);
+        let is_kernel_thread = cmdline.is_none();
+        let cmdline = cmdline.unwrap_or_else(|| name.clone());
+        let exe = self.get_process_exe(pid);
+
+        ProcessMemory {
             pid,
-            name: self.get_process_name(pid),
+            name,
+            cmdline,
             rss_kb: 0,
             pss_kb: 0,
             shared_clean_kb: 0,
@@ -208,29 +736,34 @@ impl Collector {
             private_clean_kb: 0,
             private_dirty_kb: 0,
             swap_kb: 0,
-        };
-
-        for line in content.lines() {
-            let parts: Vec<&str> = line.split_whitespace().collect();
-            if parts.len() < 2 {
-                continue;
-            }
-
-            let value = parts[1].parse::<u64>().unwrap_or(0);
-
-            match parts[0] {
-                "Rss:" => mem.rss_kb = value,
-                "Pss:" => mem.pss_kb = value,
-                "Shared_Clean:" => mem.shared_clean_kb = value,
-                "Shared_Dirty:" => mem.shared_dirty_kb = value,
-                "Private_Clean:" => mem.private_clean_kb = value,
-                "Private_Dirty:" => mem.private_dirty_kb = value,
-                "Swap:" => mem.swap_kb = value,
-                _ => {}
-            }
+            swap_pss_kb: 0,
+            anonymous_kb: 0,
+            anon_huge_kb: 0,
+            oom_score: 0,
+            oom_score_adj: 0,
+            cgroup: String::new(),
+            uid: 0,
+            username: String::new(),
+            threads: 0,
+            is_kernel_thread,
+            minor_faults: 0,
+            major_faults: 0,
+            wx_regions: 0,
+            wx_kb: 0,
+            numa_kb: BTreeMap::new(),
+            exe,
+            start_time_ticks: 0,
+            rss_only: false,
+            ppid: 0,
         }
+    }
 
-        Ok(mem)
+    // Kernel threads have no executable, and other users' processes need
+    // ptrace access, so an empty path is common without root.
+    fn get_process_exe(&self, pid: u32) -> String {
+        fs::read_link(self.proc_path.join(pid.to_string()).join("exe"))
+            .map(|path| path.to_string_lossy().into_owned())
+            .unwrap_or_default()
     }
 
     fn get_process_name(&self, pid: u32) -> String {
@@ -240,4 +773,535 @@ impl Collector {
             .map(|s| s.trim().to_string())
             .unwrap_or_else(|| format!("[{}]", pid))
     }
-}
\ No newline at end of file
+
+    fn read_proc_number(&self, pid: u32, file: &str, buf: &mut String) -> Option<i32> {
+        let path = self.proc_path.join(pid.to_string()).join(file);
+        read_into(&path, buf).ok()?;
+        buf.trim().parse().ok()
+    }
+
+    fn get_process_cmdline(&self, pid: u32) -> Option<String> {
+        let cmdline_path = self.proc_path.join(pid.to_string()).join("cmdline");
+        let raw = fs::read(cmdline_path).ok()?;
+
+        let args: Vec<String> = raw
+            .split(|&b| b == 0)
+            .filter(|arg| !arg.is_empty())
+            .map(|arg| String::from_utf8_lossy(arg).into_owned())
+            .collect();
+
+        if args.is_empty() {
+            None
+        } else {
+            Some(args.join(" "))
+        }
+    }
+
+    fn get_process_cgroup(&self, pid: u32, buf: &mut String) -> String {
+        let cgroup_path = self.proc_path.join(pid.to_string()).join("cgroup");
+        read_into(&cgroup_path, buf)
+            .map(|()| parse_cgroup(buf))
+            .unwrap_or_default()
+    }
+}
+
+// Each mapping in smaps starts with a header line, then Size: and the other
+// counters, and ends with VmFlags:, so the last Size: seen belongs to it.
+fn count_wx_regions(smaps: &str) -> (u32, u64) {
+    let mut regions = 0;
+    let mut total_kb = 0;
+    let mut size_kb = 0;
+
+    for line in smaps.lines() {
+        if let Some(size) = line.strip_prefix("Size:") {
+            size_kb = size.split_whitespace().next().and_then(|v| v.parse().ok()).unwrap_or(0);
+        } else if let Some(flags) = line.strip_prefix("VmFlags:") {
+            let flags: Vec<&str> = flags.split_whitespace().collect();
+            if flags.contains(&"wr") && flags.contains(&"ex") {
+                regions += 1;
+                total_kb += size_kb;
+            }
+        }
+    }
+
+    (regions, total_kb)
+}
+
+// Each numa_maps line gives the pages of one mapping resident on each node as
+// N<node>=<pages>, in units of that mapping's kernelpagesize_kB.
+fn parse_numa_maps(numa_maps: &str) -> BTreeMap<u32, u64> {
+    let mut nodes = BTreeMap::new();
+
+    for line in numa_maps.lines() {
+        let page_kb = line
+            .split_whitespace()
+            .find_map(|field| field.strip_prefix("kernelpagesize_kB="))
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or_else(os_utils::page_size_kb);
+
+        for field in line.split_whitespace() {
+            if let Some((node, pages)) = field.strip_prefix('N').and_then(|f| f.split_once('='))
+                && let (Ok(node), Ok(pages)) = (node.parse::<u32>(), pages.parse::<u64>())
+            {
+                *nodes.entry(node).or_insert(0) += pages * page_kb;
+            }
+        }
+    }
+
+    nodes
+}
+
+// cgroup v2 writes "max" for no limit, which fails the parse; v1 writes a
+// page-rounded i64::MAX, which the caller drops as larger than the host.
+fn read_cgroup_memory(dir: &Path, limit: &str, usage: &str, inactive_key: &str) -> Option<CgroupMemory> {
+    let read_bytes = |file: &str| {
+        fs::read_to_string(dir.join(file))
+            .ok()?
+            .trim()
+            .parse::<u64>()
+            .ok()
+    };
+
+    let limit_bytes = read_bytes(limit)?;
+    let usage_bytes = read_bytes(usage)?;
+    let inactive_file_bytes = fs::read_to_string(dir.join("memory.stat"))
+        .ok()
+        .and_then(|stat| {
+            stat.lines()
+                .find_map(|line| line.strip_prefix(inactive_key)?.strip_prefix(' ')?.parse().ok())
+        })
+        .unwrap_or(0);
+
+    Some(CgroupMemory {
+        limit_kb: limit_bytes / 1024,
+        usage_kb: usage_bytes / 1024,
+        inactive_file_kb: inactive_file_bytes / 1024,
+    })
+}
+
+// Columns: key shmid perms size cpid lpid nattch uid gid cuid cgid atime
+// dtime ctime rss swap, sizes in bytes. rss and swap only exist on 4.x+.
+fn parse_shm_line(line: &str) -> Option<ShmSegment> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    let number = |i: usize| fields.get(i).and_then(|f| f.parse::<u64>().ok());
+
+    Some(ShmSegment {
+        key: fields.first()?.parse().ok()?,
+        shmid: fields.get(1)?.parse().ok()?,
+        size_kb: number(3)? / 1024,
+        rss_kb: number(14).unwrap_or(0) / 1024,
+        swap_kb: number(15).unwrap_or(0) / 1024,
+        attached: fields.get(6)?.parse().ok()?,
+        creator_pid: fields.get(4)?.parse().ok()?,
+        uid: fields.get(7)?.parse().ok()?,
+        owner: String::new(),
+    })
+}
+
+// Fields of /proc/<pid>/stat after the parenthesised comm, which may itself
+// contain spaces or parentheses. Index 0 is field 3 (state) in proc(5).
+fn stat_fields(content: &str) -> Vec<&str> {
+    content
+        .rfind(')')
+        .map(|end| content[end + 1..].split_whitespace().collect())
+        .unwrap_or_default()
+}
+
+// Fills the memory counters of mem; the header line, fields memz doesn't use
+// and lines whose value doesn't parse are skipped.
+fn parse_smaps_rollup(smaps: &str, mem: &mut ProcessMemory) {
+    let mut swap_pss = None;
+
+    for line in smaps.lines() {
+        let mut parts = line.split_whitespace();
+        let (Some(key), Some(Ok(value))) = (parts.next(), parts.next().map(str::parse::<u64>))
+        else {
+            continue;
+        };
+
+        match key {
+            "Rss:" => mem.rss_kb = value,
+            "Pss:" => mem.pss_kb = value,
+            "Shared_Clean:" => mem.shared_clean_kb = value,
+            "Shared_Dirty:" => mem.shared_dirty_kb = value,
+            "Private_Clean:" => mem.private_clean_kb = value,
+            "Private_Dirty:" => mem.private_dirty_kb = value,
+            "Swap:" => mem.swap_kb = value,
+            "SwapPss:" => swap_pss = Some(value),
+            "Anonymous:" => mem.anonymous_kb = value,
+            "AnonHugePages:" => mem.anon_huge_kb = value,
+            _ => {}
+        }
+    }
+
+    // SwapPss only exists since 4.15; older kernels get the unshared figure.
+    mem.swap_pss_kb = swap_pss.unwrap_or(mem.swap_kb);
+}
+
+// Unparsable values are skipped rather than failing the whole snapshot; each
+// anomaly is returned as a warning, which the collector keeps with --debug.
+fn parse_meminfo(content: &str) -> (SystemMemory, Vec<String>) {
+    let mut mem = SystemMemory {
+        total_kb: 0,
+        free_kb: 0,
+        available_kb: 0,
+        buffers_kb: 0,
+        cached_kb: 0,
+        swap_total_kb: 0,
+        swap_free_kb: 0,
+        slab_kb: 0,
+        page_tables_kb: 0,
+        hugepages_total_kb: 0,
+        hugepages_free_kb: 0,
+        anon_hugepages_kb: 0,
+        shmem_kb: 0,
+        anon_pages_kb: 0,
+        mapped_kb: 0,
+        dirty_kb: 0,
+        writeback_kb: 0,
+        commit_limit_kb: 0,
+        committed_as_kb: 0,
+        overcommit_memory: None,
+        cgroup_limit: None,
+        zswap: None,
+    };
+
+    // HugePages_Total/Free are page counts, scaled by Hugepagesize below.
+    let mut hugepages_total = 0;
+    let mut hugepages_free = 0;
+    let mut hugepage_size_kb = 0;
+    let mut has_zswap = false;
+    let mut zswap_pool_kb = 0;
+    let mut zswap_stored_kb = 0;
+    let mut warnings = Vec::new();
+
+    for line in content.lines() {
+        let mut parts = line.split_whitespace();
+        let Some(key) = parts.next() else {
+            continue;
+        };
+
+        let field = match key {
+            "MemTotal:" => &mut mem.total_kb,
+            "MemFree:" => &mut mem.free_kb,
+            "MemAvailable:" => &mut mem.available_kb,
+            "Buffers:" => &mut mem.buffers_kb,
+            "Cached:" => &mut mem.cached_kb,
+            "SwapTotal:" => &mut mem.swap_total_kb,
+            "SwapFree:" => &mut mem.swap_free_kb,
+            "Slab:" => &mut mem.slab_kb,
+            "PageTables:" => &mut mem.page_tables_kb,
+            "AnonHugePages:" => &mut mem.anon_hugepages_kb,
+            "Shmem:" => &mut mem.shmem_kb,
+            "AnonPages:" => &mut mem.anon_pages_kb,
+            "Mapped:" => &mut mem.mapped_kb,
+            "Dirty:" => &mut mem.dirty_kb,
+            "Writeback:" => &mut mem.writeback_kb,
+            "CommitLimit:" => &mut mem.commit_limit_kb,
+            "Committed_AS:" => &mut mem.committed_as_kb,
+            "HugePages_Total:" => &mut hugepages_total,
+            "HugePages_Free:" => &mut hugepages_free,
+            "Hugepagesize:" => &mut hugepage_size_kb,
+            "Zswap:" => {
+                has_zswap = true;
+                &mut zswap_pool_kb
+            }
+            "Zswapped:" => &mut zswap_stored_kb,
+            _ => continue,
+        };
+
+        let Some(value) = parts.next().and_then(|v| v.parse::<u64>().ok()) else {
+            warnings.push(format!("meminfo: unparsable value in {:?}", line));
+            continue;
+        };
+        *field = value;
+
+        // HugePages_* are page counts; every other field is in kB.
+        let expected = if key.starts_with("HugePages_") { None } else { Some("kB") };
+        if parts.next() != expected {
+            warnings.push(format!("meminfo: unexpected unit in {:?}", line));
+        }
+    }
+
+    mem.hugepages_total_kb = hugepages_total * hugepage_size_kb;
+    mem.hugepages_free_kb = hugepages_free * hugepage_size_kb;
+    mem.zswap = has_zswap.then_some(Zswap {
+        pool_kb: zswap_pool_kb,
+        stored_kb: zswap_stored_kb,
+    });
+
+    (mem, warnings)
+}
+
+fn read_into(path: &Path, buf: &mut String) -> io::Result<()> {
+    buf.clear();
+    fs::File::open(path)?.read_to_string(buf)?;
+    Ok(())
+}
+
+fn parse_uid(status: &str) -> Option<u32> {
+    status_value(status, "Uid:")?.split_whitespace().next()?.parse().ok()
+}
+
+fn status_kb(status: &str, key: &str) -> Option<u64> {
+    status_value(status, key)?.split_whitespace().next()?.parse().ok()
+}
+
+fn status_value<'a>(content: &'a str, key: &str) -> Option<&'a str> {
+    content
+        .lines()
+        .find_map(|line| line.strip_prefix(key))
+        .map(str::trim)
+}
+
+// Lines are `hierarchy-id:controllers:path`; cgroup v2 uses `0::path`, v1 lists
+// one line per controller hierarchy. A container ID anywhere wins over the path.
+fn parse_cgroup(content: &str) -> String {
+    let entries: Vec<(&str, &str)> = content
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(3, ':');
+            let _id = parts.next()?;
+            let controllers = parts.next()?;
+            let path = parts.next()?;
+            Some((controllers, path))
+        })
+        .collect();
+
+    if let Some(id) = entries.iter().find_map(|(_, path)| container_id(path)) {
+        return id;
+    }
+
+    let preferred = |wanted: &str| {
+        entries
+            .iter()
+            .find(|(controllers, path)| {
+                *path != "/" && controllers.split(',').any(|c| c == wanted)
+            })
+            .map(|(_, path)| path.to_string())
+    };
+
+    entries
+        .iter()
+        .find(|(controllers, path)| controllers.is_empty() && *path != "/")
+        .map(|(_, path)| path.to_string())
+        .or_else(|| preferred("memory"))
+        .or_else(|| preferred("name=systemd"))
+        .or_else(|| {
+            entries
+                .iter()
+                .find(|(_, path)| *path != "/")
+                .map(|(_, path)| path.to_string())
+        })
+        .unwrap_or_default()
+}
+
+fn container_id(path: &str) -> Option<String> {
+    path.rsplit('/').find_map(|segment| {
+        let segment = segment.strip_suffix(".scope").unwrap_or(segment);
+        let id = ["docker-", "cri-containerd-", "crio-", "libpod-"]
+            .iter()
+            .find_map(|prefix| segment.strip_prefix(prefix))
+            .unwrap_or(segment);
+
+        if id.len() == 64 && id.chars().all(|c| c.is_ascii_hexdigit()) {
+            Some(id[..12].to_string())
+        } else {
+            None
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> String {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name);
+        fs::read_to_string(&path).unwrap_or_else(|err| panic!("{}: {}", path.display(), err))
+    }
+
+    fn smaps_rollup(name: &str) -> ProcessMemory {
+        let mut mem = ProcessMemory::default();
+        parse_smaps_rollup(&fixture(name), &mut mem);
+        mem
+    }
+
+    #[test]
+    fn meminfo_normal() {
+        let (mem, warnings) = parse_meminfo(&fixture("meminfo/normal"));
+
+        assert_eq!(mem.total_kb, 8053172);
+        assert_eq!(mem.free_kb, 1234567);
+        assert_eq!(mem.available_kb, 5432100);
+        assert_eq!(mem.buffers_kb, 204800);
+        assert_eq!(mem.cached_kb, 3145728);
+        assert_eq!(mem.swap_total_kb, 2097148);
+        assert_eq!(mem.swap_free_kb, 1048574);
+        assert_eq!(mem.shmem_kb, 262144);
+        assert_eq!((mem.anon_pages_kb, mem.mapped_kb), (1800000, 400000));
+        assert_eq!(mem.slab_kb, 409600);
+        assert_eq!(mem.page_tables_kb, 20480);
+        assert_eq!(mem.anon_hugepages_kb, 131072);
+        assert_eq!((mem.dirty_kb, mem.writeback_kb), (512, 128));
+        assert_eq!((mem.commit_limit_kb, mem.committed_as_kb), (6123732, 7340032));
+        assert_eq!(mem.hugepages_total_kb, 8 * 2048);
+        assert_eq!(mem.hugepages_free_kb, 2 * 2048);
+
+        let zswap = mem.zswap.expect("zswap fields present");
+        assert_eq!((zswap.pool_kb, zswap.stored_kb), (10240, 40960));
+        assert!(mem.cgroup_limit.is_none());
+        assert!(warnings.is_empty(), "{:?}", warnings);
+    }
+
+    #[test]
+    fn meminfo_missing_fields_default_to_zero() {
+        let (mem, warnings) = parse_meminfo(&fixture("meminfo/missing_fields"));
+
+        assert_eq!(mem.total_kb, 2048000);
+        assert_eq!(mem.free_kb, 512000);
+        assert_eq!(mem.available_kb, 0);
+        assert_eq!(mem.shmem_kb, 0);
+        assert_eq!(mem.hugepages_total_kb, 0);
+        assert!(mem.zswap.is_none());
+        assert!(warnings.is_empty(), "{:?}", warnings);
+    }
+
+    #[test]
+    fn meminfo_ignores_unknown_fields() {
+        let (mem, warnings) = parse_meminfo(&fixture("meminfo/extra_fields"));
+
+        assert_eq!(mem.total_kb, 4096000);
+        assert_eq!(mem.free_kb, 1024000);
+        assert_eq!(mem.available_kb, 2048000);
+        assert_eq!(mem.cached_kb, 512000);
+        assert!(warnings.is_empty(), "{:?}", warnings);
+    }
+
+    #[test]
+    fn meminfo_malformed_lines_warn() {
+        let (mem, warnings) = parse_meminfo(&fixture("meminfo/malformed"));
+
+        assert_eq!(mem.total_kb, 4096000);
+        assert_eq!(mem.free_kb, 0);
+        assert_eq!(mem.available_kb, 0);
+        assert_eq!(mem.buffers_kb, 0);
+        assert_eq!(mem.swap_total_kb, 1024000);
+        // The value is kept, but a unit other than kB is reported.
+        assert_eq!(mem.cached_kb, 512000);
+        assert_eq!(mem.hugepages_total_kb, 0);
+
+        let unparsable = warnings.iter().filter(|w| w.contains("unparsable value")).count();
+        let unit = warnings.iter().filter(|w| w.contains("unexpected unit")).count();
+        assert_eq!((unparsable, unit), (3, 2), "{:?}", warnings);
+    }
+
+    #[test]
+    fn smaps_rollup_normal() {
+        let mem = smaps_rollup("smaps_rollup/normal");
+
+        assert_eq!(mem.rss_kb, 20480);
+        assert_eq!(mem.pss_kb, 12288);
+        assert_eq!(mem.shared_clean_kb, 6144);
+        assert_eq!(mem.shared_dirty_kb, 2048);
+        assert_eq!(mem.private_clean_kb, 4096);
+        assert_eq!(mem.private_dirty_kb, 8192);
+        assert_eq!(mem.anonymous_kb, 10240);
+        assert_eq!(mem.anon_huge_kb, 4096);
+        assert_eq!(mem.swap_kb, 1024);
+        assert_eq!(mem.swap_pss_kb, 512);
+    }
+
+    #[test]
+    fn smaps_rollup_without_swap_pss_falls_back_to_swap() {
+        let mem = smaps_rollup("smaps_rollup/missing_fields");
+
+        assert_eq!(mem.rss_kb, 4096);
+        assert_eq!(mem.pss_kb, 2048);
+        assert_eq!(mem.shared_clean_kb, 0);
+        assert_eq!(mem.private_dirty_kb, 1024);
+        assert_eq!(mem.anonymous_kb, 0);
+        assert_eq!(mem.swap_kb, 256);
+        assert_eq!(mem.swap_pss_kb, 256);
+    }
+
+    #[test]
+    fn smaps_rollup_skips_malformed_lines() {
+        let mem = smaps_rollup("smaps_rollup/malformed");
+
+        // A later unparsable Rss: must not clobber the good one.
+        assert_eq!(mem.rss_kb, 4096);
+        assert_eq!(mem.pss_kb, 0);
+        assert_eq!(mem.shared_clean_kb, 0);
+        assert_eq!(mem.private_clean_kb, 1024);
+        assert_eq!(mem.swap_pss_kb, 0);
+    }
+
+    #[test]
+    fn collects_from_proc_root() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/proc");
+        let mut collector = Collector::new()
+            .unwrap()
+            .with_proc_root(Some(root))
+            .with_threads(Some(1));
+        let snapshot = collector.collect().unwrap();
+
+        assert_eq!(snapshot.system.total_kb, 8053172);
+        assert!(snapshot.numa_nodes.is_empty());
+        assert_eq!(snapshot.processes.len(), 3);
+
+        let proc = snapshot.processes.iter().find(|p| p.pid == 4242).unwrap();
+        assert!(!proc.rss_only);
+        assert_eq!(proc.name, "worker");
+        assert_eq!(proc.cmdline, "/usr/bin/worker --threads 4");
+        assert!(!proc.is_kernel_thread);
+        assert_eq!(proc.pss_kb, 12288);
+        assert_eq!(proc.swap_pss_kb, 512);
+        assert_eq!(proc.uid, 1000);
+        assert_eq!(proc.threads, 3);
+        assert_eq!((proc.minor_faults, proc.major_faults), (1500, 7));
+        assert_eq!(proc.start_time_ticks, 12345);
+        assert_eq!(proc.ppid, 1);
+        assert_eq!((proc.oom_score, proc.oom_score_adj), (200, 0));
+        assert_eq!(proc.cgroup, "/user.slice");
+    }
+
+    #[test]
+    fn falls_back_to_status_without_smaps_rollup() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/proc");
+        let mut collector = Collector::new()
+            .unwrap()
+            .with_proc_root(Some(root))
+            .with_threads(Some(1));
+        let snapshot = collector.collect().unwrap();
+
+        let proc = snapshot.processes.iter().find(|p| p.pid == 4243).unwrap();
+        assert!(proc.rss_only);
+        assert_eq!(proc.name, "postgres");
+        assert_eq!(proc.rss_kb, 20480);
+        assert_eq!(proc.anonymous_kb, 12000);
+        assert_eq!(proc.pss_kb, 0);
+        assert_eq!((proc.swap_kb, proc.swap_pss_kb), (256, 256));
+        assert_eq!(proc.uid, 999);
+        assert_eq!(snapshot.processes_unreadable, 0);
+    }
+
+    #[test]
+    fn keeps_kernel_threads_without_memory() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/proc");
+        let mut collector = Collector::new()
+            .unwrap()
+            .with_proc_root(Some(root))
+            .with_threads(Some(1));
+        let snapshot = collector.collect().unwrap();
+
+        let proc = snapshot.processes.iter().find(|p| p.pid == 2).unwrap();
+        assert!(proc.is_kernel_thread);
+        assert!(!proc.rss_only);
+        assert_eq!(proc.name, "kthreadd");
+        assert_eq!(proc.cmdline, "kthreadd");
+        assert_eq!((proc.rss_kb, proc.pss_kb), (0, 0));
+        assert_eq!(proc.threads, 1);
+        assert_eq!(snapshot.processes_skipped, 0);
+    }
+}
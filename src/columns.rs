@@ -0,0 +1,184 @@
+use anyhow::{Result, anyhow};
+use ratatui::layout::Constraint;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Column {
+    Pid,
+    User,
+    Name,
+    Pss,
+    MemPct,
+    Sharing,
+    Rss,
+    Shared,
+    SharedClean,
+    SharedDirty,
+    Private,
+    PrivateClean,
+    PrivateDirty,
+    Swap,
+    Oom,
+    Threads,
+    Delta,
+    Growth,
+    Age,
+    Thp,
+}
+
+impl Column {
+    pub const ALL: &'static [Column] = &[
+        Column::Pid,
+        Column::User,
+        Column::Name,
+        Column::Pss,
+        Column::MemPct,
+        Column::Sharing,
+        Column::Rss,
+        Column::Shared,
+        Column::SharedClean,
+        Column::SharedDirty,
+        Column::Private,
+        Column::PrivateClean,
+        Column::PrivateDirty,
+        Column::Swap,
+        Column::Oom,
+        Column::Threads,
+        Column::Delta,
+        Column::Growth,
+        Column::Age,
+        Column::Thp,
+    ];
+
+    // The clean/dirty splits, %Mem, sharing, OOM, growth, age and THP are
+    // opt-in so the default table fits the 118 cells inside the border of a
+    // 120-column terminal: the widths below plus one cell between columns.
+    pub const DEFAULT: &'static [Column] = &[
+        Column::Pid,
+        Column::User,
+        Column::Name,
+        Column::Pss,
+        Column::Rss,
+        Column::Shared,
+        Column::Private,
+        Column::Swap,
+        Column::Threads,
+        Column::Delta,
+    ];
+
+    // Columns only smaps_rollup can fill; /proc/[pid]/status has none of them.
+    pub fn needs_smaps(self) -> bool {
+        matches!(
+            self,
+            Column::Pss
+                | Column::MemPct
+                | Column::Sharing
+                | Column::Shared
+                | Column::SharedClean
+                | Column::SharedDirty
+                | Column::Private
+                | Column::PrivateClean
+                | Column::PrivateDirty
+                | Column::Thp
+        )
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Column::Pid => "pid",
+            Column::User => "user",
+            Column::Name => "name",
+            Column::Pss => "pss",
+            Column::MemPct => "mem_pct",
+            Column::Sharing => "sharing",
+            Column::Rss => "rss",
+            Column::Shared => "shared",
+            Column::SharedClean => "shared_clean",
+            Column::SharedDirty => "shared_dirty",
+            Column::Private => "private",
+            Column::PrivateClean => "private_clean",
+            Column::PrivateDirty => "private_dirty",
+            Column::Swap => "swap",
+            Column::Oom => "oom",
+            Column::Threads => "threads",
+            Column::Delta => "delta",
+            Column::Growth => "growth",
+            Column::Age => "age",
+            Column::Thp => "thp",
+        }
+    }
+
+    pub fn header(self) -> &'static str {
+        match self {
+            Column::Pid => "PID",
+            Column::User => "User",
+            Column::Name => "Name",
+            Column::Pss => "PSS",
+            Column::MemPct => "%Mem",
+            Column::Sharing => "%Shr",
+            Column::Rss => "RSS",
+            Column::Shared => "Shared",
+            Column::SharedClean => "ShClean",
+            Column::SharedDirty => "ShDirty",
+            Column::Private => "Private",
+            Column::PrivateClean => "PrClean",
+            Column::PrivateDirty => "PrDirty",
+            Column::Swap => "Swap",
+            Column::Oom => "OOM",
+            Column::Threads => "Thr",
+            Column::Delta => "Delta",
+            Column::Growth => "kB/s",
+            Column::Age => "Age",
+            Column::Thp => "THP",
+        }
+    }
+
+    pub fn constraint(self) -> Constraint {
+        match self {
+            Column::Pid => Constraint::Length(7),
+            Column::User => Constraint::Length(9),
+            Column::Name => Constraint::Min(20),
+            Column::Pss
+            | Column::Rss
+            | Column::Shared
+            | Column::SharedClean
+            | Column::SharedDirty
+            | Column::Private
+            | Column::PrivateClean
+            | Column::PrivateDirty
+            | Column::Swap
+            | Column::Thp => Constraint::Length(11),
+            Column::Delta => Constraint::Length(13),
+            Column::Growth => Constraint::Length(9),
+            Column::Oom | Column::MemPct | Column::Sharing => Constraint::Length(6),
+            Column::Threads => Constraint::Length(5),
+            Column::Age => Constraint::Length(7),
+        }
+    }
+
+    pub fn parse_list(raw: &str) -> Result<Vec<Column>> {
+        let columns = raw
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(|name| {
+                Self::ALL
+                    .iter()
+                    .copied()
+                    .find(|c| c.name() == name.to_ascii_lowercase())
+                    .ok_or_else(|| {
+                        anyhow!(
+                            "Unknown column: {} (expected any of: {})",
+                            name,
+                            Self::ALL.iter().map(|c| c.name()).collect::<Vec<_>>().join(", ")
+                        )
+                    })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        if columns.is_empty() {
+            return Err(anyhow!("--columns needs at least one column"));
+        }
+
+        Ok(columns)
+    }
+}
@@ -0,0 +1,110 @@
+use crate::cli::{self, Args};
+use crate::theme::Palette;
+use crate::ui::{SortMode, ViewMode};
+use crate::units::Unit;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// Every key is optional; a missing file is the same as an empty one.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub interval: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub theme: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub view: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub units: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hide_kernel_threads: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filter: Option<String>,
+}
+
+// $XDG_CONFIG_HOME/memz/config.toml, falling back to ~/.config like most
+// Linux tools. Under sudo HOME is usually root's, which is where it looks.
+pub fn path() -> Option<PathBuf> {
+    let base = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+    };
+    Some(base.join("memz").join("config.toml"))
+}
+
+// The starting point CLI flags are parsed over, so a flag always wins.
+pub fn load_args() -> Result<Args> {
+    let Some(path) = path() else {
+        return Ok(Args::default());
+    };
+
+    read(&path)
+        .and_then(Config::into_args)
+        .with_context(|| format!("Invalid config file {}", path.display()))
+}
+
+// Keys the caller leaves alone, such as interval, keep their value from the
+// file. Comments in it are lost, since the file is regenerated. The new file
+// is renamed over the old one so a crash never leaves it half written.
+pub fn save(update: impl FnOnce(&mut Config)) -> Result<PathBuf> {
+    let path = path().context("Neither XDG_CONFIG_HOME nor HOME is set")?;
+    let mut config = read(&path).with_context(|| format!("Invalid config file {}", path.display()))?;
+    update(&mut config);
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+
+    let tmp = path.with_extension("toml.tmp");
+    fs::write(&tmp, toml::to_string(&config)?)
+        .with_context(|| format!("Failed to write {}", tmp.display()))?;
+    fs::rename(&tmp, &path)
+        .with_context(|| format!("Failed to replace {}", path.display()))?;
+
+    Ok(path)
+}
+
+fn read(path: &Path) -> Result<Config> {
+    let raw = match fs::read_to_string(path) {
+        Ok(raw) => raw,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Config::default()),
+        Err(err) => {
+            return Err(err).with_context(|| format!("Failed to read {}", path.display()));
+        }
+    };
+
+    Ok(toml::from_str(&raw)?)
+}
+
+impl Config {
+    fn into_args(self) -> Result<Args> {
+        let mut args = Args::default();
+
+        if let Some(secs) = self.interval {
+            args.interval = cli::interval_from_secs(secs)?;
+        }
+        if let Some(theme) = &self.theme {
+            args.theme = Palette::by_name(theme)?;
+        }
+        if let Some(sort) = &self.sort {
+            args.sort = Some(SortMode::by_name(sort)?);
+        }
+        if let Some(view) = &self.view {
+            args.view = Some(ViewMode::by_name(view)?);
+        }
+        if let Some(units) = &self.units {
+            args.unit = Unit::by_name(units)?;
+        }
+        args.hide_kernel_threads = self.hide_kernel_threads.unwrap_or(false);
+        args.name_filter = self.filter.unwrap_or_default();
+
+        Ok(args)
+    }
+}
@@ -1,39 +1,247 @@
 use anyhow::Result;
+use std::collections::VecDeque;
 use std::time::{Duration, Instant};
-use crate::collector;
+use crate::collector::{self, RootProcessExited};
 use crate::analyzer;
+use crate::metrics::MetricsServer;
+use crate::recording::{Recorder, Replay, ReplayFinished};
+use crate::watchpoint::Watchpoint;
+
+pub const DEFAULT_MAX_FAILURES: u32 = 10;
+const TIMINGS_LEN: usize = 20;
+
+#[derive(Clone, Copy)]
+struct Timing {
+    collect: Duration,
+    analyze: Duration,
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct Spread {
+    pub min: Duration,
+    pub avg: Duration,
+    pub max: Duration,
+}
+
+impl Spread {
+    fn of(values: impl Iterator<Item = Duration> + Clone) -> Self {
+        let count = values.clone().count() as u32;
+        if count == 0 {
+            return Self::default();
+        }
+        Self {
+            min: values.clone().min().unwrap_or_default(),
+            avg: values.clone().sum::<Duration>() / count,
+            max: values.max().unwrap_or_default(),
+        }
+    }
+}
+
+pub struct TimingStats {
+    pub samples: usize,
+    pub interval: Duration,
+    pub collect: Spread,
+    pub analyze: Spread,
+}
 
 pub struct Engine {
     collector: collector::Collector,
     analyzer: analyzer::Analyzer,
     tick_rate: Duration,
     last_tick: Instant,
+    metrics: Option<MetricsServer>,
+    recorder: Option<Recorder>,
+    replay: Option<Replay>,
+    watchpoint: Option<Watchpoint>,
+    warnings: Vec<String>,
+    failures: u32,
+    max_failures: u32,
+    timings: VecDeque<Timing>,
 }
 
 impl Engine {
-    pub fn new(tick_rate: Duration) -> Result<Self> {
+    pub fn new(tick_rate: Duration, collector: collector::Collector) -> Result<Self> {
         Ok(Self {
-            collector: collector::Collector::new()?,
-            analyzer: analyzer::Analyzer::new(),
+            collector,
+            analyzer: analyzer::Analyzer::new(analyzer::DEFAULT_HISTORY_LEN),
             tick_rate,
             last_tick: Instant::now(),
+            metrics: None,
+            recorder: None,
+            replay: None,
+            watchpoint: None,
+            warnings: Vec::new(),
+            failures: 0,
+            max_failures: DEFAULT_MAX_FAILURES,
+            timings: VecDeque::with_capacity(TIMINGS_LEN),
         })
     }
 
+    pub fn with_history(mut self, history_len: Option<usize>) -> Self {
+        if let Some(len) = history_len {
+            self.analyzer.set_history_len(len);
+        }
+        self
+    }
+
+    pub fn with_max_failures(mut self, max_failures: Option<u32>) -> Self {
+        if let Some(max) = max_failures {
+            self.max_failures = max.max(1);
+        }
+        self
+    }
+
+    pub fn consecutive_failures(&self) -> u32 {
+        self.failures
+    }
+
+    // Decides whether a failed tick can be ridden out: Ok means keep the last
+    // good state and try again next interval. The end of a replay or of the
+    // --pid root is final, as is a failure streak reaching --max-failures.
+    pub fn tolerate(&self, err: anyhow::Error) -> Result<()> {
+        if err.downcast_ref::<ReplayFinished>().is_some()
+            || err.downcast_ref::<RootProcessExited>().is_some()
+        {
+            return Err(err);
+        }
+
+        if self.failures >= self.max_failures {
+            return Err(err.context(format!(
+                "Giving up after {} consecutive failed collections",
+                self.failures
+            )));
+        }
+
+        Ok(())
+    }
+
+    pub fn with_metrics(mut self, metrics: Option<MetricsServer>) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    pub fn with_recorder(mut self, recorder: Option<Recorder>) -> Self {
+        self.recorder = recorder;
+        self
+    }
+
+    pub fn with_replay(mut self, replay: Option<Replay>) -> Self {
+        self.replay = replay;
+        self
+    }
+
+    pub fn with_watchpoint(mut self, watchpoint: Option<Watchpoint>) -> Self {
+        self.watchpoint = watchpoint;
+        self
+    }
+
+    // Parse anomalies from --debug, drained by whoever displays them.
+    pub fn take_warnings(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.warnings)
+    }
+
     pub fn should_tick(&self) -> bool {
         self.last_tick.elapsed() >= self.tick_rate
     }
 
+    pub fn until_next_tick(&self) -> Duration {
+        self.tick_rate.saturating_sub(self.last_tick.elapsed())
+    }
+
     pub fn tick(&mut self) -> Result<analyzer::AnalyzedState> {
-        let data = self.collector.collect()?;
-        self.analyzer.update(data);
+        // A failed collection still waits a full interval before retrying.
+        let started = Instant::now();
+        let data = self.collect();
         self.last_tick = Instant::now();
-        Ok(self.analyzer.get_state())
+
+        match data {
+            Ok(_) => self.failures = 0,
+            Err(_) => self.failures += 1,
+        }
+
+        let state = self.analyze(data?, started);
+        Ok(self.publish(state))
+    }
+
+    // While the display is paused only the watchpoint keeps sampling, so a
+    // trace has no gap; the analyzer, recorder and metrics keep their last
+    // state. A paused replay stays where it is.
+    pub fn tick_paused(&mut self) -> Result<()> {
+        let Some(watchpoint) = &mut self.watchpoint else {
+            return Ok(());
+        };
+        if self.replay.is_some() {
+            return Ok(());
+        }
+
+        self.last_tick = Instant::now();
+        let snapshot = self.collector.collect()?;
+        self.warnings.extend(self.collector.take_warnings());
+        watchpoint.write(&snapshot.processes)
     }
 
     pub fn initial_state(&mut self) -> Result<analyzer::AnalyzedState> {
-        let data = self.collector.collect()?;
-        self.analyzer.update(data);
-        Ok(self.analyzer.get_state())
+        let started = Instant::now();
+        let data = self.collect()?;
+        let state = self.analyze(data, started);
+        Ok(self.publish(state))
+    }
+
+    // Collection time close to the interval means memz is collecting
+    // back to back, which is what these numbers are for spotting.
+    pub fn timing_stats(&self) -> TimingStats {
+        TimingStats {
+            samples: self.timings.len(),
+            interval: self.tick_rate,
+            collect: Spread::of(self.timings.iter().map(|t| t.collect)),
+            analyze: Spread::of(self.timings.iter().map(|t| t.analyze)),
+        }
+    }
+
+    fn analyze(
+        &mut self,
+        data: collector::MemorySnapshot,
+        started: Instant,
+    ) -> analyzer::AnalyzedState {
+        let collected = Instant::now();
+        let state = self.analyzer.analyze(data);
+
+        if self.timings.len() == TIMINGS_LEN {
+            self.timings.pop_front();
+        }
+        self.timings.push_back(Timing {
+            collect: collected - started,
+            analyze: collected.elapsed(),
+        });
+        state
+    }
+
+    pub fn summary(&self) -> analyzer::SessionSummary {
+        self.analyzer.summary()
+    }
+
+    fn collect(&mut self) -> Result<collector::MemorySnapshot> {
+        let snapshot = match &mut self.replay {
+            Some(replay) => replay.next()?,
+            None => self.collector.collect()?,
+        };
+        self.warnings.extend(self.collector.take_warnings());
+
+        if let Some(recorder) = &mut self.recorder {
+            recorder.write(&snapshot)?;
+        }
+
+        if let Some(watchpoint) = &mut self.watchpoint {
+            watchpoint.write(&snapshot.processes)?;
+        }
+
+        Ok(snapshot)
+    }
+
+    fn publish(&self, state: analyzer::AnalyzedState) -> analyzer::AnalyzedState {
+        if let Some(metrics) = &self.metrics {
+            metrics.publish(&state);
+        }
+        state
     }
 }
@@ -0,0 +1,49 @@
+use crate::{analyzer::ProcessStats, clock};
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
+pub(crate) fn write_process_csv(processes: &[ProcessStats]) -> Result<PathBuf> {
+    let path = PathBuf::from(format!("memz-processes-{}.csv", clock::file_timestamp(clock::unix_now())));
+    let file = File::create(&path)
+        .with_context(|| format!("Failed to create {}", path.display()))?;
+    let mut out = BufWriter::new(file);
+
+    writeln!(
+        out,
+        "pid,name,pss_kb,rss_kb,shared_kb,shared_clean_kb,shared_dirty_kb,private_kb,private_clean_kb,private_dirty_kb,swap_kb,oom_score,threads,pss_delta_kb"
+    )?;
+
+    for proc in processes {
+        writeln!(
+            out,
+            "{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+            proc.pid,
+            csv_field(&proc.name),
+            proc.pss_kb,
+            proc.rss_kb,
+            proc.shared_kb,
+            proc.shared_clean_kb,
+            proc.shared_dirty_kb,
+            proc.private_kb,
+            proc.private_clean_kb,
+            proc.private_dirty_kb,
+            proc.swap_kb,
+            proc.oom_score,
+            proc.threads,
+            proc.pss_delta_kb,
+        )?;
+    }
+
+    out.flush()?;
+    Ok(path)
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
@@ -0,0 +1,156 @@
+use crate::{
+    analyzer::AnalyzedState,
+    clock,
+    engine::Engine,
+    ui::{self, SortMode},
+    units::{Unit, fmt_kb},
+};
+use anyhow::Result;
+use ratatui::{Terminal, backend::TestBackend};
+use serde::Serialize;
+use std::io::{self, Write};
+use std::thread;
+
+#[derive(Serialize)]
+struct StreamRecord<'a> {
+    timestamp: u64,
+    #[serde(flatten)]
+    state: &'a AnalyzedState,
+}
+
+pub(crate) fn print_json(engine: &mut Engine, top: usize, sort: SortMode) -> Result<()> {
+    let mut state = engine.initial_state()?;
+    state.limit_processes(top, |p| sort.sort_processes(p));
+    report_warnings(engine);
+
+    let mut stdout = io::stdout().lock();
+    serde_json::to_writer(&mut stdout, &state)?;
+    writeln!(stdout)?;
+
+    Ok(())
+}
+
+const DUMP_WIDTH: u16 = 120;
+const DUMP_HEIGHT: u16 = 40;
+
+// Renders the TUI into an in-memory buffer and prints its cells as plain
+// text, one line per row, with trailing blanks trimmed.
+pub(crate) fn dump_frame(engine: &mut Engine, mut app: ui::App) -> Result<()> {
+    app.update_data(engine.initial_state()?);
+    app.push_warnings(engine.take_warnings());
+
+    let mut terminal = Terminal::new(TestBackend::new(DUMP_WIDTH, DUMP_HEIGHT))?;
+    terminal.draw(|f| ui::draw(f, &mut app))?;
+
+    let buffer = terminal.backend().buffer();
+    let mut stdout = io::stdout().lock();
+    for row in buffer.content.chunks(DUMP_WIDTH as usize) {
+        let line: String = row.iter().map(|cell| cell.symbol()).collect();
+        writeln!(stdout, "{}", line.trim_end())?;
+    }
+
+    Ok(())
+}
+
+pub const DEFAULT_CHECK_THRESHOLD_PCT: f64 = 90.0;
+// CRITICAL in the Nagios plugin convention; errors still exit 1.
+const CHECK_EXCEEDED_EXIT: i32 = 2;
+
+pub(crate) fn check(engine: &mut Engine, threshold_pct: Option<f64>) -> Result<()> {
+    let threshold = threshold_pct.unwrap_or(DEFAULT_CHECK_THRESHOLD_PCT);
+    let state = engine.initial_state()?;
+    report_warnings(engine);
+
+    let sys = &state.system;
+    let used_pct = if sys.total_kb > 0 {
+        sys.used_kb as f64 / sys.total_kb as f64 * 100.0
+    } else {
+        0.0
+    };
+    let exceeded = used_pct >= threshold;
+
+    println!(
+        "memz {}: {:.1}% of memory used ({} of {}, threshold {}%)",
+        if exceeded { "CRITICAL" } else { "OK" },
+        used_pct,
+        fmt_kb(sys.used_kb, Unit::Auto),
+        fmt_kb(sys.total_kb, Unit::Auto),
+        threshold
+    );
+
+    if exceeded {
+        io::stdout().flush()?;
+        std::process::exit(CHECK_EXCEEDED_EXIT);
+    }
+
+    Ok(())
+}
+
+pub(crate) fn stream_json(engine: &mut Engine, top: usize, sort: SortMode) -> Result<()> {
+    let mut stdout = io::stdout().lock();
+    let mut state = Some(engine.initial_state()?);
+    report_warnings(engine);
+
+    loop {
+        // A failed tick prints nothing rather than repeating stale data.
+        if let Some(state) = &mut state {
+            state.limit_processes(top, |p| sort.sort_processes(p));
+            let record = StreamRecord {
+                timestamp: clock::unix_now(),
+                state,
+            };
+
+            if let Err(err) = write_line(&mut stdout, &record) {
+                return match err.downcast_ref::<io::Error>() {
+                    Some(io_err) if io_err.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+                    _ => Err(err),
+                };
+            }
+        }
+
+        thread::sleep(engine.until_next_tick());
+        state = tick(engine)?;
+        report_warnings(engine);
+    }
+}
+
+fn tick(engine: &mut Engine) -> Result<Option<AnalyzedState>> {
+    match engine.tick() {
+        Ok(state) => Ok(Some(state)),
+        Err(err) => {
+            let message = format!("{:#}", err);
+            engine.tolerate(err)?;
+            eprintln!(
+                "memz: collection failed ({} in a row): {}",
+                engine.consecutive_failures(),
+                message
+            );
+            Ok(None)
+        }
+    }
+}
+
+// Without a TUI there is nowhere else to show --debug output.
+fn report_warnings(engine: &mut Engine) {
+    for warning in engine.take_warnings() {
+        eprintln!("memz: {}", warning);
+    }
+}
+
+fn write_line<W: Write, T: Serialize>(out: &mut W, value: &T) -> Result<()> {
+    serde_json::to_writer(&mut *out, value).map_err(io::Error::from)?;
+    writeln!(out)?;
+    out.flush()?;
+    Ok(())
+}
+
+pub(crate) fn serve_metrics(engine: &mut Engine) -> Result<()> {
+    engine.initial_state()?;
+    report_warnings(engine);
+
+    loop {
+        thread::sleep(engine.until_next_tick());
+        tick(engine)?;
+        report_warnings(engine);
+    }
+}
@@ -1,26 +1,132 @@
 use crate::{
-    engine::Engine, os_utils::check_kernel_version, sysreq::check_system_requirements, tui::Tui,
+    cli::Args,
+    collector::{Collector, RootProcessExited},
+    engine::Engine,
+    metrics::MetricsServer,
+    os_utils::check_kernel_version,
+    recording::{Recorder, Replay, ReplayFinished},
+    sysreq::check_system_requirements,
+    tui::Tui,
+    ui::SortMode,
+    watchpoint::Watchpoint,
 };
 use anyhow::Result;
-use std::time::Duration;
 
 pub(crate) mod analyzer;
+mod cli;
+mod clipboard;
+mod clock;
+mod columns;
+mod config;
 pub(crate) mod collector;
 mod engine;
+mod export;
+mod headless;
+mod metrics;
 mod os_utils;
+mod recording;
+mod report;
 mod sysreq;
+mod theme;
 mod tui;
 mod ui;
-
-const TICK_RATE: Duration = Duration::from_millis(1000);
+mod units;
+mod watchpoint;
 
 fn main() -> Result<()> {
-    check_system_requirements()?;
-    check_kernel_version()?;
+    let args = Args::parse()?;
+
+    // A replay never touches /proc and a --proc-root tree is plain files, so
+    // both also work off-Linux and unprivileged.
+    if args.replay.is_none() && args.proc_root.is_none() {
+        check_system_requirements(!args.no_root)?;
+        check_kernel_version()?;
+    }
+
+    let collector = Collector::new()?
+        .with_proc_root(args.proc_root)
+        .with_root_pid(args.pid)
+        .with_threads(args.threads)
+        .with_debug(args.debug)
+        .with_wx_scan(args.wx_scan)
+        .with_numa_scan(args.numa_scan);
+
+    let no_smaps_rollup = args.replay.is_none() && !collector.smaps_rollup_supported();
+    if no_smaps_rollup {
+        eprintln!("Warning: this kernel has no /proc/[pid]/smaps_rollup (added in 4.14)");
+        eprintln!("Showing RSS from /proc/[pid]/status instead; PSS is unavailable\n");
+    }
+    let collector = collector.with_fast(args.fast || no_smaps_rollup);
+    let metrics = args.metrics_port.map(MetricsServer::start).transpose()?;
+    let recorder = args.record.map(Recorder::create).transpose()?;
+    let replay = args.replay.map(Replay::open).transpose()?;
+    let watchpoint = match (args.watch_pid, args.watch_log) {
+        (Some(pid), Some(path)) => Some(Watchpoint::create(pid, path, args.watch_follow)?),
+        _ => None,
+    };
+    let mut engine = Engine::new(args.interval, collector)?
+        .with_history(args.history)
+        .with_max_failures(args.max_failures)
+        .with_metrics(metrics)
+        .with_recorder(recorder)
+        .with_replay(replay)
+        .with_watchpoint(watchpoint);
 
-    let engine = Engine::new(TICK_RATE)?;
-    let mut tui = Tui::new(engine)?;
-    tui.run()?;
+    let result = if args.check {
+        headless::check(&mut engine, args.threshold_pct)
+    } else if args.json {
+        headless::print_json(&mut engine, args.top, args.sort.unwrap_or(SortMode::Pss))
+    } else if args.stream {
+        headless::stream_json(&mut engine, args.top, args.sort.unwrap_or(SortMode::Pss))
+    } else if args.no_tui {
+        headless::serve_metrics(&mut engine)
+    } else {
+        let app = ui::App::new(args.theme, args.columns)
+            .with_interval(args.interval)
+            .with_unit(args.unit)
+            .with_delta(args.bold_delta_kb, args.delta_kb)
+            .with_confirm_quit(args.confirm_quit)
+            .with_debug(args.debug)
+            .with_wx_scan(args.wx_scan)
+            .with_numa_scan(args.numa_scan)
+            .with_fast(args.fast || no_smaps_rollup)
+            .with_no_smaps_rollup(no_smaps_rollup)
+            .with_sort(args.sort)
+            .with_view(args.view)
+            .with_hide_kernel_threads(args.hide_kernel_threads)
+            .with_name_filter(args.name_filter)
+            .with_partial(args.no_root)
+            .with_alert_pss(args.alert_pss_mb)
+            .with_on_alert(args.on_alert);
+        if args.dump_frame {
+            headless::dump_frame(&mut engine, app)
+        } else {
+            Tui::new(engine, app).and_then(|mut tui| {
+                if args.once {
+                    tui.run_once()?;
+                } else {
+                    tui.run()?;
+                }
+                let summary = tui.summary();
+                // Dropping the TUI restores the terminal, so the summary lands
+                // on the normal screen instead of the alternate one.
+                drop(tui);
+                if args.summary {
+                    println!("{}", serde_json::to_string_pretty(&summary)?);
+                }
+                Ok(())
+            })
+        }
+    };
 
-    Ok(())
+    match result {
+        Err(err)
+            if err.downcast_ref::<RootProcessExited>().is_some()
+                || err.downcast_ref::<ReplayFinished>().is_some() =>
+        {
+            eprintln!("{}", err);
+            Ok(())
+        }
+        other => other,
+    }
 }
@@ -0,0 +1,115 @@
+use crate::analyzer::{AnalyzedState, ProcessStats};
+use anyhow::{Context, Result};
+use std::fmt::Write as _;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+type ProcessGauge = (&'static str, &'static str, fn(&ProcessStats) -> u64);
+
+pub struct MetricsServer {
+    body: Arc<Mutex<String>>,
+}
+
+impl MetricsServer {
+    pub fn start(port: u16) -> Result<Self> {
+        let listener = TcpListener::bind(("0.0.0.0", port))
+            .with_context(|| format!("Failed to bind metrics port {}", port))?;
+        let body = Arc::new(Mutex::new(String::new()));
+        let shared = Arc::clone(&body);
+
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let _ = handle_connection(stream, &shared);
+            }
+        });
+
+        Ok(Self { body })
+    }
+
+    pub fn publish(&self, state: &AnalyzedState) {
+        let rendered = render(state);
+        if let Ok(mut body) = self.body.lock() {
+            *body = rendered;
+        }
+    }
+}
+
+fn handle_connection(mut stream: TcpStream, body: &Mutex<String>) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+
+    let mut request_line = String::new();
+    BufReader::new(&stream).read_line(&mut request_line)?;
+    let path = request_line.split_whitespace().nth(1).unwrap_or("");
+
+    let (status, content_type, payload) = if path == "/metrics" {
+        let payload = body.lock().map(|b| b.clone()).unwrap_or_default();
+        ("200 OK", "text/plain; version=0.0.4", payload)
+    } else {
+        ("404 Not Found", "text/plain", String::from("Not Found\n"))
+    };
+
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        payload.len(),
+        payload
+    )?;
+    stream.flush()
+}
+
+fn render(state: &AnalyzedState) -> String {
+    let sys = &state.system;
+    let mut out = String::new();
+
+    let gauges = [
+        ("memz_memory_total_bytes", "Total physical memory", sys.total_kb),
+        ("memz_memory_used_bytes", "Memory in use (total minus available)", sys.used_kb),
+        ("memz_memory_available_bytes", "Memory available for new allocations", sys.available_kb),
+        ("memz_swap_total_bytes", "Total swap space", sys.swap_total_kb),
+        ("memz_swap_used_bytes", "Swap space in use", sys.swap_used_kb),
+    ];
+
+    for (name, help, kb) in gauges {
+        let _ = writeln!(out, "# HELP {} {}", name, help);
+        let _ = writeln!(out, "# TYPE {} gauge", name);
+        let _ = writeln!(out, "{} {}", name, kb * 1024);
+    }
+
+    let _ = writeln!(out, "# HELP memz_sharing_efficiency_percent Share of summed RSS saved by page sharing");
+    let _ = writeln!(out, "# TYPE memz_sharing_efficiency_percent gauge");
+    let _ = writeln!(out, "memz_sharing_efficiency_percent {:.2}", state.shared_memory.sharing_efficiency);
+
+    let per_process: [ProcessGauge; 2] = [
+        ("memz_process_pss_bytes", "Proportional set size per process", |p| p.pss_kb),
+        ("memz_process_rss_bytes", "Resident set size per process", |p| p.rss_kb),
+    ];
+
+    for (name, help, value) in per_process {
+        let _ = writeln!(out, "# HELP {} {}", name, help);
+        let _ = writeln!(out, "# TYPE {} gauge", name);
+        for proc in &state.processes {
+            let _ = writeln!(
+                out,
+                "{}{{pid=\"{}\",name=\"{}\"}} {}",
+                name,
+                proc.pid,
+                escape_label(&proc.name),
+                value(proc) * 1024
+            );
+        }
+    }
+
+    out
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
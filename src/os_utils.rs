@@ -1,4 +1,10 @@
 use anyhow::Result;
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static SHUTDOWN_REQUESTED: AtomicBool = AtomicBool::new(false);
+static PAGE_SIZE_KB: OnceLock<u64> = OnceLock::new();
 
 #[cfg(target_os = "linux")]
 const KERNEL_RELEASE_PATH: &str = "/proc/sys/kernel/osrelease";
@@ -35,11 +41,119 @@ fn read_kernel_version() -> (u32, u32) {
     (major, minor)
 }
 
+// Process start times in /proc/[pid]/stat are counted in these ticks.
+#[cfg(target_os = "linux")]
+pub(crate) fn clock_ticks_per_sec() -> u64 {
+    match unsafe { libc::sysconf(libc::_SC_CLK_TCK) } {
+        ticks if ticks > 0 => ticks as u64,
+        _ => 100,
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn clock_ticks_per_sec() -> u64 {
+    100
+}
+
+// 4 KiB on x86, but arm64 kernels can be built with 16 or 64 KiB pages.
+#[cfg(target_os = "linux")]
+pub(crate) fn page_size_kb() -> u64 {
+    *PAGE_SIZE_KB.get_or_init(|| match unsafe { libc::sysconf(libc::_SC_PAGESIZE) } {
+        bytes if bytes >= 1024 => bytes as u64 / 1024,
+        _ => 4,
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn page_size_kb() -> u64 {
+    *PAGE_SIZE_KB.get_or_init(|| 4)
+}
+
 #[cfg(target_os = "linux")]
 pub(crate) fn is_root() -> bool {
     unsafe { libc::geteuid() == 0 }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Signal {
+    Term,
+    Kill,
+}
+
+impl Signal {
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Signal::Term => "SIGTERM",
+            Signal::Kill => "SIGKILL",
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub(crate) fn send_signal(pid: u32, signal: Signal) -> std::io::Result<()> {
+    if pid <= 1 || pid == std::process::id() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::PermissionDenied,
+            format!("refusing to signal PID {}", pid),
+        ));
+    }
+
+    let sig = match signal {
+        Signal::Term => libc::SIGTERM,
+        Signal::Kill => libc::SIGKILL,
+    };
+
+    if unsafe { libc::kill(pid as libc::pid_t, sig) } == 0 {
+        Ok(())
+    } else {
+        Err(std::io::Error::last_os_error())
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn send_signal(_pid: u32, _signal: Signal) -> std::io::Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "signals are only supported on Linux",
+    ))
+}
+
+// Output is discarded so the command can't draw over the TUI, and a thread
+// reaps it so it doesn't linger as a zombie.
+pub(crate) fn spawn_detached(program: &str, args: &[String]) -> std::io::Result<()> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    std::thread::spawn(move || child.wait());
+    Ok(())
+}
+
+// In raw mode Ctrl-C arrives as a key press, but a SIGINT or SIGTERM from
+// elsewhere would otherwise kill us with the terminal still in raw mode.
+// The handler only sets a flag; the TUI loop returns and Drop restores it.
+#[cfg(target_os = "linux")]
+pub(crate) fn install_shutdown_handler() {
+    extern "C" fn on_signal(_: libc::c_int) {
+        SHUTDOWN_REQUESTED.store(true, Ordering::SeqCst);
+    }
+
+    let handler = on_signal as extern "C" fn(libc::c_int) as libc::sighandler_t;
+    unsafe {
+        libc::signal(libc::SIGINT, handler);
+        libc::signal(libc::SIGTERM, handler);
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn install_shutdown_handler() {}
+
+pub(crate) fn shutdown_requested() -> bool {
+    SHUTDOWN_REQUESTED.load(Ordering::SeqCst)
+}
+
 #[cfg(target_os = "linux")]
 fn kernel_too_old(major: u32, minor: u32) -> bool {
     major < 4 || (major == 4 && minor < 14)
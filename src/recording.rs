@@ -0,0 +1,110 @@
+use crate::{clock, collector::MemorySnapshot};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Lines, Write};
+use std::path::PathBuf;
+
+#[derive(Serialize)]
+struct RecordLine<'a> {
+    timestamp: u64,
+    #[serde(flatten)]
+    snapshot: &'a MemorySnapshot,
+}
+
+#[derive(Deserialize)]
+struct ReplayLine {
+    #[serde(flatten)]
+    snapshot: MemorySnapshot,
+}
+
+#[derive(Debug)]
+pub struct ReplayFinished {
+    path: PathBuf,
+    snapshots: usize,
+}
+
+impl fmt::Display for ReplayFinished {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Replay of {} finished after {} snapshots",
+            self.path.display(),
+            self.snapshots
+        )
+    }
+}
+
+impl std::error::Error for ReplayFinished {}
+
+// One JSON object per line, flushed every tick so a recording survives the
+// monitored box crashing mid-capture.
+pub struct Recorder {
+    out: BufWriter<File>,
+}
+
+impl Recorder {
+    pub fn create(path: PathBuf) -> Result<Self> {
+        let file = File::create(&path)
+            .with_context(|| format!("Failed to create recording {}", path.display()))?;
+        Ok(Self {
+            out: BufWriter::new(file),
+        })
+    }
+
+    pub fn write(&mut self, snapshot: &MemorySnapshot) -> Result<()> {
+        let line = RecordLine {
+            timestamp: clock::unix_now(),
+            snapshot,
+        };
+        serde_json::to_writer(&mut self.out, &line)?;
+        writeln!(self.out)?;
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+pub struct Replay {
+    path: PathBuf,
+    lines: Lines<BufReader<File>>,
+    line_no: usize,
+    snapshots: usize,
+}
+
+impl Replay {
+    pub fn open(path: PathBuf) -> Result<Self> {
+        let file = File::open(&path)
+            .with_context(|| format!("Failed to open recording {}", path.display()))?;
+        Ok(Self {
+            path,
+            lines: BufReader::new(file).lines(),
+            line_no: 0,
+            snapshots: 0,
+        })
+    }
+
+    pub fn next(&mut self) -> Result<MemorySnapshot> {
+        loop {
+            let Some(line) = self.lines.next() else {
+                return Err(ReplayFinished {
+                    path: self.path.clone(),
+                    snapshots: self.snapshots,
+                }
+                .into());
+            };
+
+            let line = line?;
+            self.line_no += 1;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let parsed: ReplayLine = serde_json::from_str(&line).with_context(|| {
+                format!("Invalid snapshot on line {} of {}", self.line_no, self.path.display())
+            })?;
+            self.snapshots += 1;
+            return Ok(parsed.snapshot);
+        }
+    }
+}
@@ -0,0 +1,199 @@
+use crate::{
+    analyzer::AnalyzedState,
+    clock,
+    units::{fmt_exact_kb, group_thousands},
+};
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
+pub(crate) fn write_report(state: &AnalyzedState) -> Result<PathBuf> {
+    let now = clock::unix_now();
+    let path = PathBuf::from(format!("memz-report-{}.txt", clock::file_timestamp(now)));
+    let file = File::create(&path)
+        .with_context(|| format!("Failed to create {}", path.display()))?;
+    let mut out = BufWriter::new(file);
+
+    writeln!(out, "MEMZ memory report, {} ({})", clock::time_of_day(now), now)?;
+    writeln!(out)?;
+
+    write_system(&mut out, state)?;
+    write_memory_map(&mut out, state)?;
+    write_shared_memory(&mut out, state)?;
+    write_processes(&mut out, state)?;
+
+    out.flush()?;
+    Ok(path)
+}
+
+fn gib(kb: u64) -> f64 {
+    kb as f64 / 1024.0 / 1024.0
+}
+
+fn write_system(out: &mut impl Write, state: &AnalyzedState) -> Result<()> {
+    let sys = &state.system;
+
+    writeln!(out, "== System Memory ==")?;
+    writeln!(out, "Memory:        {:.1} / {:.1} GiB", gib(sys.used_kb), gib(sys.total_kb))?;
+    writeln!(out, "Available:     {:.1} GiB", gib(sys.available_kb))?;
+    writeln!(out, "Cache/Buffers: {:.1} GiB", gib(sys.cached_kb + sys.buffers_kb))?;
+    writeln!(
+        out,
+        "Swap:          {:.1} / {:.1} GiB (process SwapPss {:.1} GiB)",
+        gib(sys.swap_used_kb),
+        gib(sys.swap_total_kb),
+        gib(sys.total_process_swap_pss_kb)
+    )?;
+    writeln!(
+        out,
+        "Process PSS:   {:.1} GiB | RSS: {:.1} GiB",
+        gib(sys.total_process_pss_kb),
+        gib(sys.total_process_rss_kb)
+    )?;
+
+    if let Some(zswap) = &sys.zswap {
+        writeln!(
+            out,
+            "Zswap:         {:.1} GiB stored in {:.1} GiB compressed",
+            gib(zswap.stored_kb),
+            gib(zswap.pool_kb)
+        )?;
+    }
+
+    if let Some(commit) = &sys.commit {
+        writeln!(
+            out,
+            "Commit:        {:.1} GiB / {:.1} GiB limit ({:.0}%){}",
+            gib(commit.committed_kb),
+            gib(commit.limit_kb),
+            commit.ratio_pct,
+            if commit.exceeded() { " OVERCOMMITTED" } else { "" }
+        )?;
+    }
+
+    if let Some(rates) = &sys.swap_rates {
+        writeln!(
+            out,
+            "Swap I/O:      in {:.0} pages/s | out {:.0} pages/s | major faults {:.0}/s",
+            rates.swap_in_per_s, rates.swap_out_per_s, rates.major_faults_per_s,
+        )?;
+    }
+
+    if let Some(psi) = &sys.pressure {
+        writeln!(
+            out,
+            "Pressure:      some {:.2} / {:.2} / {:.2} | full {:.2} / {:.2} / {:.2} (avg10/60/300 %)",
+            psi.some_avg10, psi.some_avg60, psi.some_avg300, psi.full_avg10, psi.full_avg60, psi.full_avg300,
+        )?;
+    }
+
+    writeln!(out)?;
+    Ok(())
+}
+
+fn write_memory_map(out: &mut impl Write, state: &AnalyzedState) -> Result<()> {
+    let map = &state.memory_map;
+    let total = state.system.host_total_kb;
+
+    writeln!(out, "== Memory Map ==")?;
+
+    let items = [
+        ("Kernel", map.kernel_kb),
+        ("Process Private", map.process_private_kb),
+        ("Process Shared", map.process_shared_kb),
+        ("Page Cache", map.cache_kb),
+        ("Buffers", map.buffers_kb),
+        ("Slab", map.slab_kb),
+        ("Page Tables", map.page_tables_kb),
+        ("Free", map.free_kb),
+        ("HugePages Used", map.hugepages_used_kb),
+        ("HugePages Free", map.hugepages_free_kb),
+    ];
+
+    for (label, kb) in items {
+        let pct = if total > 0 {
+            kb as f64 / total as f64 * 100.0
+        } else {
+            0.0
+        };
+        writeln!(out, "{:16} {:7.1} GiB ({:5.1}%)", label, gib(kb), pct)?;
+    }
+
+    writeln!(out, "Total:           {:.1} GiB", gib(total))?;
+    match map.reconciliation_error_kb {
+        Some(error_kb) => writeln!(
+            out,
+            "Reconciliation:  process PSS vs AnonPages + Mapped, discrepancy {:+.1} MiB",
+            error_kb as f64 / 1024.0
+        )?,
+        None => writeln!(out, "Reconciliation:  n/a (needs the PSS of every process)")?,
+    }
+
+    for node in &state.numa_nodes {
+        writeln!(
+            out,
+            "NUMA node {}:     {:.1} / {:.1} GiB",
+            node.node_id,
+            gib(node.mem_used_kb),
+            gib(node.mem_total_kb)
+        )?;
+    }
+
+    writeln!(out)?;
+    Ok(())
+}
+
+fn write_shared_memory(out: &mut impl Write, state: &AnalyzedState) -> Result<()> {
+    let shared = &state.shared_memory;
+
+    writeln!(out, "== Shared Memory ==")?;
+    writeln!(out, "Total Shared:       {:.1} GiB", gib(shared.total_shared_kb))?;
+    writeln!(out, "  Clean:            {:.1} GiB", gib(shared.total_shared_clean_kb))?;
+    writeln!(out, "  Dirty:            {:.1} GiB", gib(shared.total_shared_dirty_kb))?;
+    writeln!(out, "Sharing Efficiency: {:.1}%", shared.sharing_efficiency)?;
+    writeln!(out, "Shmem (tmpfs+shm):  {:.1} GiB", gib(shared.shmem_kb))?;
+
+    for segment in &shared.shm_segments {
+        writeln!(
+            out,
+            "  SysV 0x{:08x} shmid {:<8} {:>13}, {} attached, owner {}",
+            segment.key,
+            segment.shmid,
+            fmt_exact_kb(segment.size_kb),
+            segment.attached,
+            segment.owner
+        )?;
+    }
+
+    writeln!(out)?;
+    Ok(())
+}
+
+fn write_processes(out: &mut impl Write, state: &AnalyzedState) -> Result<()> {
+    writeln!(out, "== Processes ({}) ==", state.processes.len())?;
+    writeln!(
+        out,
+        "{:>7} {:10} {:20} {:>11} {:>11} {:>11} {:>11} {:>11} {:>5} {:>4}",
+        "PID", "User", "Name", "PSS kB", "RSS kB", "Shared kB", "Private kB", "Swap kB", "OOM", "Thr"
+    )?;
+
+    for proc in &state.processes {
+        writeln!(
+            out,
+            "{:>7} {:10} {:20} {:>11} {:>11} {:>11} {:>11} {:>11} {:>5} {:>4}",
+            proc.pid,
+            proc.username,
+            proc.name,
+            group_thousands(proc.pss_kb),
+            group_thousands(proc.rss_kb),
+            group_thousands(proc.shared_kb),
+            group_thousands(proc.private_kb),
+            group_thousands(proc.swap_kb),
+            proc.oom_score,
+            proc.threads,
+        )?;
+    }
+
+    Ok(())
+}
@@ -3,7 +3,7 @@ use anyhow::Result;
 #[cfg(target_os = "linux")]
 use crate::os_utils::is_root;
 
-pub(crate) fn check_system_requirements() -> Result<()> {
+pub(crate) fn check_system_requirements(require_root: bool) -> Result<()> {
     #[cfg(not(target_os = "linux"))]
     {
         eprintln!("Error: This tool only runs on Linux");
@@ -20,9 +20,9 @@ pub(crate) fn check_system_requirements() -> Result<()> {
     }
 
     #[cfg(target_os = "linux")]
-    if !is_root() {
+    if require_root && !is_root() {
         return Err(anyhow::anyhow!(
-            "This tool requires root privileges. Please run with sudo."
+            "This tool requires root privileges. Please run with sudo, or pass --no-root for partial data."
         ));
     }
 
@@ -0,0 +1,100 @@
+use anyhow::{Result, anyhow};
+use ratatui::style::Color;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Palette {
+    pub name: &'static str,
+    pub primary: Color,
+    pub secondary: Color,
+    pub leak: Color,
+    pub error: Color,
+    pub ok: Color,
+    pub warn: Color,
+    pub critical: Color,
+    pub used: Color,
+    pub cache: Color,
+    pub free: Color,
+    pub alert: Color,
+    pub grow: Color,
+    pub shrink: Color,
+}
+
+impl Palette {
+    pub const THEMES: &'static [&'static str] = &["default", "light", "mono"];
+
+    pub fn by_name(name: &str) -> Result<Self> {
+        match name {
+            "default" => Ok(Self::default()),
+            "light" => Ok(Self {
+                name: "light",
+                primary: Color::Black,
+                secondary: Color::Blue,
+                leak: Color::Magenta,
+                error: Color::Red,
+                ok: Color::Green,
+                warn: Color::Rgb(176, 112, 0),
+                critical: Color::Red,
+                used: Color::Green,
+                cache: Color::Blue,
+                free: Color::Gray,
+                alert: Color::Magenta,
+                grow: Color::Red,
+                shrink: Color::Green,
+            }),
+            "mono" => Ok(Self {
+                name: "mono",
+                primary: Color::Reset,
+                secondary: Color::Reset,
+                leak: Color::Reset,
+                error: Color::Reset,
+                ok: Color::Reset,
+                warn: Color::Reset,
+                critical: Color::Reset,
+                used: Color::Reset,
+                cache: Color::Reset,
+                free: Color::Reset,
+                alert: Color::Reset,
+                grow: Color::Reset,
+                shrink: Color::Reset,
+            }),
+            other => Err(anyhow!(
+                "Unknown theme: {} (expected one of: {})",
+                other,
+                Self::THEMES.join(", ")
+            )),
+        }
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self {
+            name: "default",
+            primary: Color::White,
+            secondary: Color::Yellow,
+            leak: Color::Red,
+            error: Color::Red,
+            ok: Color::Green,
+            warn: Color::Yellow,
+            critical: Color::Red,
+            used: Color::Green,
+            cache: Color::Blue,
+            free: Color::DarkGray,
+            alert: Color::Magenta,
+            grow: Color::LightRed,
+            shrink: Color::LightGreen,
+        }
+    }
+}
+
+impl Palette {
+    pub fn level(&self, pct: f64, warn_pct: f64, critical_pct: f64) -> Color {
+        if pct >= critical_pct {
+            self.critical
+        } else if pct >= warn_pct {
+            self.warn
+        } else {
+            self.ok
+        }
+    }
+}
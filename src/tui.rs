@@ -1,12 +1,21 @@
-use crate::{engine::Engine, ui};
+use crate::{
+    analyzer,
+    engine::Engine,
+    os_utils::{self, Signal},
+    recording::ReplayFinished,
+    ui,
+};
 use anyhow::Result;
 use crossterm::{
-    event::{self, Event, KeyCode},
+    event::{
+        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, MouseButton,
+        MouseEvent, MouseEventKind,
+    },
     execute,
     terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
 };
 use ratatui::{Terminal, backend::CrosstermBackend};
-use std::io;
+use std::io::{self, Write};
 
 pub struct Tui {
     terminal: Terminal<CrosstermBackend<io::Stdout>>,
@@ -15,10 +24,11 @@ pub struct Tui {
 }
 
 impl Tui {
-    pub fn new(engine: Engine) -> Result<Self> {
+    pub fn new(engine: Engine, app: ui::App) -> Result<Self> {
+        os_utils::install_shutdown_handler();
         enable_raw_mode()?;
         let mut stdout = io::stdout();
-        execute!(stdout, EnterAlternateScreen)?;
+        execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
 
         let backend = CrosstermBackend::new(stdout);
         let terminal = Terminal::new(backend)?;
@@ -26,39 +36,186 @@ impl Tui {
         let mut tui = Self {
             terminal,
             engine,
-            app: ui::App::new(),
+            app,
         };
 
         let initial = tui.engine.initial_state()?;
         tui.app.update_data(initial);
+        tui.app.push_warnings(tui.engine.take_warnings());
+        tui.app.set_timings(tui.engine.timing_stats());
 
         Ok(tui)
     }
 
+    pub fn summary(&self) -> analyzer::SessionSummary {
+        self.engine.summary()
+    }
+
     pub fn run(&mut self) -> Result<()> {
         loop {
+            if os_utils::shutdown_requested() {
+                return Ok(());
+            }
+
             self.terminal.draw(|f| ui::draw(f, &mut self.app))?;
 
             if event::poll(std::time::Duration::from_millis(50))? {
-                if let Event::Key(k) = event::read()? {
-                    match k.code {
-                        KeyCode::Char('q') => return Ok(()),
+                match event::read()? {
+                    Event::Key(k) if self.app.has_pending_kill() => match k.code {
+                        KeyCode::Char('y') => self.app.confirm_kill(),
+                        _ => self.app.cancel_kill(),
+                    },
+                    Event::Key(_) if self.app.is_help_open() => self.app.toggle_help(),
+                    Event::Key(k) if self.app.has_pending_quit() => match k.code {
+                        KeyCode::Char('y') => return Ok(()),
+                        _ => self.app.cancel_quit(),
+                    },
+                    Event::Key(k) if self.app.is_editing_filter() => match k.code {
+                        KeyCode::Char(c) => self.app.filter_push(c),
+                        KeyCode::Backspace => self.app.filter_pop(),
+                        KeyCode::Enter => self.app.finish_filter(),
+                        KeyCode::Esc => self.app.cancel_filter(),
+                        _ => {}
+                    },
+                    Event::Key(k) => match k.code {
+                        KeyCode::Char('q') if self.app.request_quit() => return Ok(()),
+                        KeyCode::Char('p') => self.toggle_pause()?,
+                        KeyCode::Char(' ') | KeyCode::F(5) => self.refresh_now()?,
                         KeyCode::Char('n') => self.app.next_sort(),
-                        KeyCode::Char('v') => self.app.toggle_view(),
+                        KeyCode::Char('o') => self.app.flip_sort(),
+                        KeyCode::Char('D') => self.app.toggle_diagnostics(),
+                        KeyCode::Char('?') => self.app.toggle_help(),
+                        KeyCode::Char('c') => self.app.toggle_compact(),
+                        KeyCode::Char('x') => self.app.toggle_full_names(),
+                        KeyCode::Char('v') | KeyCode::Right => self.app.toggle_view(),
+                        KeyCode::Char('V') | KeyCode::BackTab | KeyCode::Left => {
+                            self.app.toggle_view_back()
+                        }
+                        KeyCode::Char(c @ '1'..='9') => {
+                            self.app.select_view(c.to_digit(10).unwrap_or(0) as usize)
+                        }
+                        KeyCode::Char('e') => self.app.export_csv(),
+                        KeyCode::Char('r') => self.app.export_report(),
+                        KeyCode::Char('S') => self.app.save_preferences(),
+                        KeyCode::Char('f') => self.app.toggle_cgroup_filter(),
+                        KeyCode::Char('U') => self.app.toggle_user_filter(),
+                        KeyCode::Char('/') => self.app.start_filter(),
+                        KeyCode::Char('w') => self.app.toggle_watch(),
+                        KeyCode::Char('y') => self.app.copy_selected_pid(),
+                        KeyCode::Char('t') => self.app.toggle_kernel_threads(),
+                        KeyCode::Char('u') => self.app.next_unit(),
+                        KeyCode::Char('l') => self.app.next_bar_scale(),
+                        KeyCode::Char('b') => self.app.capture_baseline(),
+                        KeyCode::Char('B') => self.app.toggle_baseline(),
+                        KeyCode::Char('a') => self.app.capture_snapshot(),
+                        KeyCode::Char('k') => self.app.request_kill(Signal::Term),
+                        KeyCode::Char('K') => self.app.request_kill(Signal::Kill),
                         KeyCode::Up => self.app.scroll_up(),
                         KeyCode::Down => self.app.scroll_down(),
                         KeyCode::PageUp => self.app.page_up(),
                         KeyCode::PageDown => self.app.page_down(),
+                        KeyCode::Home | KeyCode::Char('g') => self.app.scroll_top(),
+                        KeyCode::End | KeyCode::Char('G') => self.app.scroll_bottom(),
+                        KeyCode::Enter => self.app.open_detail(),
+                        KeyCode::Esc => self.app.close_detail(),
                         _ => {}
+                    },
+                    Event::Mouse(m) => self.handle_mouse(m),
+                    Event::Resize(_, _) => {
+                        // Redraw right away; draw recomputes visible_rows for
+                        // the new size and clamps the scroll offset to it.
+                        self.terminal.autoresize()?;
+                        self.terminal.draw(|f| ui::draw(f, &mut self.app))?;
                     }
+                    _ => {}
                 }
             }
 
             if self.engine.should_tick() {
-                let state = self.engine.tick()?;
+                if !self.app.is_paused() {
+                    self.refresh()?;
+                } else if let Err(err) = self.engine.tick_paused() {
+                    self.app.watchpoint_failed(format!("{:#}", err));
+                }
+            }
+
+            if self.app.take_bell() {
+                let out = self.terminal.backend_mut();
+                out.write_all(b"\x07")?;
+                out.flush()?;
+            }
+        }
+    }
+
+    // Holds the frame until a key is pressed so it can be captured, redrawing
+    // only if the terminal is resized.
+    pub fn run_once(&mut self) -> Result<()> {
+        self.terminal.draw(|f| ui::draw(f, &mut self.app))?;
+
+        while !os_utils::shutdown_requested() {
+            if !event::poll(std::time::Duration::from_millis(50))? {
+                continue;
+            }
+
+            match event::read()? {
+                Event::Key(_) => break,
+                Event::Resize(_, _) => {
+                    self.terminal.autoresize()?;
+                    self.terminal.draw(|f| ui::draw(f, &mut self.app))?;
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    fn handle_mouse(&mut self, m: MouseEvent) {
+        if self.app.has_pending_kill() || self.app.has_pending_quit() {
+            return;
+        }
+
+        match m.kind {
+            MouseEventKind::ScrollUp => self.app.scroll_up(),
+            MouseEventKind::ScrollDown => self.app.scroll_down(),
+            MouseEventKind::Down(MouseButton::Left) => self.app.click(m.column, m.row),
+            _ => {}
+        }
+    }
+
+    fn toggle_pause(&mut self) -> Result<()> {
+        if !self.app.toggle_pause() {
+            self.refresh()?;
+        }
+
+        Ok(())
+    }
+
+    fn refresh_now(&mut self) -> Result<()> {
+        self.refresh()?;
+        self.app.refreshed_manually();
+        Ok(())
+    }
+
+    // At the end of a replay the last frame stays on screen, paused.
+    fn refresh(&mut self) -> Result<()> {
+        match self.engine.tick() {
+            Ok(state) => {
                 self.app.update_data(state);
+                self.app.push_warnings(self.engine.take_warnings());
+                self.app.set_timings(self.engine.timing_stats());
+            }
+            Err(err) if err.downcast_ref::<ReplayFinished>().is_some() => {
+                self.app.finish_replay(err.to_string())
+            }
+            Err(err) => {
+                let message = format!("{:#}", err);
+                self.engine.tolerate(err)?;
+                self.app.collection_failed(message, self.engine.consecutive_failures());
             }
         }
+
+        Ok(())
     }
 }
 
@@ -71,7 +228,11 @@ impl Drop for Tui {
             eprintln!("Failed to disable raw mode: {:?}", err);
         }
 
-        if let Err(err) = execute!(self.terminal.backend_mut(), LeaveAlternateScreen) {
+        if let Err(err) = execute!(
+            self.terminal.backend_mut(),
+            LeaveAlternateScreen,
+            DisableMouseCapture
+        ) {
             eprintln!("Failed to leave alternate screen: {:?}", err);
         }
 
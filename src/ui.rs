@@ -1,416 +1,3171 @@
-use crate::analyzer::AnalyzedState;
+use crate::analyzer::{
+    self, AnalyzedState, Change, GroupedStats, ProcessDiff, ProcessKey, ProcessStats,
+    SYSTEM_HISTORY_LEN, TreeRow,
+};
+use crate::clipboard::Clipboard;
+use crate::columns::Column;
+use crate::engine::TimingStats;
+use crate::os_utils::{self, Signal};
+use crate::theme::Palette;
+use crate::units::{Unit, fmt_exact_kb, fmt_kb, fmt_signed_exact_kb, fmt_signed_kb};
+use crate::{clock, config, export, report};
+use anyhow::{Result, anyhow};
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Paragraph, Row, Table},
+    symbols::Marker,
+    widgets::{
+        Axis, Block, Borders, Cell, Chart, Clear, Dataset, Gauge, GraphType, Paragraph, Row,
+        Sparkline, Table, Tabs,
+    },
     Frame,
 };
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::{Duration, Instant};
 
-const COLOR_PRIMARY: Color = Color::White;
-const COLOR_SECONDARY: Color = Color::Yellow;
+const RECONCILIATION_WARN_KB: i64 = 256 * 1024;
+const USAGE_WARN_PCT: f64 = 80.0;
+const DIAGNOSTICS_LEN: usize = 200;
+const DEFAULT_BOLD_DELTA_KB: u64 = 10240;
+const USAGE_CRITICAL_PCT: f64 = 90.0;
+const STATUS_TIMEOUT: Duration = Duration::from_secs(5);
+const ALERT_HOOK_COOLDOWN: Duration = Duration::from_secs(60);
+const NUMA_TOP_CONSUMERS: usize = 10;
+// The divider and the total row under the process table.
+const TOTAL_ROW_LINES: u16 = 2;
+const DETAIL_CMDLINE_LINES: usize = 8;
+// comm is cut to 15 bytes by the kernel (TASK_COMM_LEN minus the NUL).
+const COMM_LEN: usize = 15;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum SortMode {
     Pss,
     Rss,
     Shared,
+    Swap,
+    PrivateDirty,
     Pid,
+    OomScore,
+    Threads,
+    Growth,
+    Age,
+    Thp,
+}
+
+impl SortMode {
+    // Sorts in the mode's natural direction. The sorts are stable, so
+    // presorting by PID breaks ties and rows with equal values keep their
+    // order from tick to tick.
+    pub(crate) fn sort_processes(self, processes: &mut [ProcessStats]) {
+        processes.sort_by_key(|p| p.pid);
+        match self {
+            SortMode::Pss => processes.sort_by_key(|p| std::cmp::Reverse(p.pss_kb)),
+            SortMode::Rss => processes.sort_by_key(|p| std::cmp::Reverse(p.rss_kb)),
+            SortMode::Shared => processes.sort_by_key(|p| std::cmp::Reverse(p.shared_kb)),
+            SortMode::Swap => processes.sort_by_key(|p| std::cmp::Reverse(p.swap_kb)),
+            SortMode::PrivateDirty => {
+                processes.sort_by_key(|p| std::cmp::Reverse(p.private_dirty_kb))
+            }
+            SortMode::Pid => {}
+            SortMode::OomScore => processes.sort_by_key(|p| std::cmp::Reverse(p.oom_score)),
+            SortMode::Threads => processes.sort_by_key(|p| std::cmp::Reverse(p.threads)),
+            SortMode::Growth => {
+                processes.sort_by(|a, b| b.growth_rate_kb_s.total_cmp(&a.growth_rate_kb_s))
+            }
+            SortMode::Age => processes.sort_by_key(|p| std::cmp::Reverse(p.age_secs)),
+            SortMode::Thp => processes.sort_by_key(|p| std::cmp::Reverse(p.anon_huge_kb)),
+        }
+    }
+
+    const ALL: &'static [SortMode] = &[
+        SortMode::Pss,
+        SortMode::Rss,
+        SortMode::Shared,
+        SortMode::Swap,
+        SortMode::PrivateDirty,
+        SortMode::Pid,
+        SortMode::OomScore,
+        SortMode::Threads,
+        SortMode::Growth,
+        SortMode::Age,
+        SortMode::Thp,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SortMode::Pss => "pss",
+            SortMode::Rss => "rss",
+            SortMode::Shared => "shared",
+            SortMode::Swap => "swap",
+            SortMode::PrivateDirty => "private_dirty",
+            SortMode::Pid => "pid",
+            SortMode::OomScore => "oom",
+            SortMode::Threads => "threads",
+            SortMode::Growth => "growth",
+            SortMode::Age => "age",
+            SortMode::Thp => "thp",
+        }
+    }
+
+    pub fn by_name(name: &str) -> Result<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|mode| mode.name() == name.to_ascii_lowercase())
+            .ok_or_else(|| {
+                anyhow!(
+                    "Unknown sort mode: {} (expected one of: {})",
+                    name,
+                    Self::ALL.iter().map(|m| m.name()).collect::<Vec<_>>().join(", ")
+                )
+            })
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum ViewMode {
     Processes,
+    Tree,
+    Grouped,
+    Users,
     MemoryMap,
     SharedMemory,
+    Security,
+    Numa,
+    Diff,
+}
+
+impl ViewMode {
+    // The views that exist without --wx-scan, --numa-scan or snapshots, so
+    // any of them can be the one memz starts in.
+    const STARTUP: &'static [ViewMode] = &[
+        ViewMode::Processes,
+        ViewMode::Tree,
+        ViewMode::Grouped,
+        ViewMode::Users,
+        ViewMode::MemoryMap,
+        ViewMode::SharedMemory,
+    ];
+
+    fn title(self) -> &'static str {
+        match self {
+            ViewMode::Processes => "Processes",
+            ViewMode::Tree => "Tree",
+            ViewMode::Grouped => "Grouped",
+            ViewMode::Users => "Users",
+            ViewMode::MemoryMap => "Memory Map",
+            ViewMode::SharedMemory => "Shared Memory",
+            ViewMode::Security => "Security",
+            ViewMode::Numa => "NUMA",
+            ViewMode::Diff => "Snapshot Diff",
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ViewMode::Processes => "processes",
+            ViewMode::Tree => "tree",
+            ViewMode::Grouped => "grouped",
+            ViewMode::Users => "users",
+            ViewMode::MemoryMap => "map",
+            ViewMode::SharedMemory => "shared",
+            ViewMode::Security => "security",
+            ViewMode::Numa => "numa",
+            ViewMode::Diff => "diff",
+        }
+    }
+
+    pub fn by_name(name: &str) -> Result<Self> {
+        Self::STARTUP
+            .iter()
+            .copied()
+            .find(|view| view.name() == name.to_ascii_lowercase())
+            .ok_or_else(|| {
+                anyhow!(
+                    "Unknown view: {} (expected one of: {})",
+                    name,
+                    Self::STARTUP.iter().map(|v| v.name()).collect::<Vec<_>>().join(", ")
+                )
+            })
+    }
+}
+
+// How the memory map sizes its bars. The percentages next to them are
+// always linear; log and relative only keep small categories visible.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BarScale {
+    Linear,
+    Log,
+    Relative,
+}
+
+impl BarScale {
+    fn next(self) -> Self {
+        match self {
+            BarScale::Linear => BarScale::Log,
+            BarScale::Log => BarScale::Relative,
+            BarScale::Relative => BarScale::Linear,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            BarScale::Linear => "linear",
+            BarScale::Log => "log",
+            BarScale::Relative => "relative to largest",
+        }
+    }
+
+    // A nonzero category gets at least one character outside linear mode.
+    fn width(self, pct: f64, max_pct: f64, full: f64) -> usize {
+        let fraction = match self {
+            BarScale::Linear => return (pct / 100.0 * full) as usize,
+            BarScale::Log => (1.0 + pct * 10.0).ln() / 1001f64.ln(),
+            BarScale::Relative if max_pct > 0.0 => pct / max_pct,
+            BarScale::Relative => 0.0,
+        };
+        let width = (fraction * full) as usize;
+        if pct > 0.0 { width.max(1) } else { width }
+    }
 }
 
 pub struct App {
+    theme: Palette,
+    columns: Vec<Column>,
+    unit: Unit,
+    bar_scale: BarScale,
+    bold_delta_kb: u64,
+    delta_in_kb: bool,
     state: AnalyzedState,
+    processes: Vec<ProcessStats>,
+    cgroup_filter: Option<String>,
+    user_filter: Option<(u32, String)>,
+    name_filter: String,
+    editing_filter: bool,
+    hide_kernel_threads: bool,
     sort_mode: SortMode,
+    ascending: bool,
     view_mode: ViewMode,
     scroll_offset: usize,
     visible_rows: usize,
+    table_area: Rect,
+    selected: usize,
+    selected_pid: Option<u32>,
+    show_detail: bool,
+    status: Option<(String, Instant)>,
+    started: Instant,
+    last_update: Instant,
+    stale_after: Duration,
+    failures: u32,
+    paused_at: Option<u64>,
+    baseline: Option<Baseline>,
+    snapshot_a: Option<Snapshot>,
+    snapshot_b: Option<Snapshot>,
+    snapshot_diff: Vec<ProcessDiff>,
+    show_baseline: bool,
+    pending_kill: Option<PendingKill>,
+    confirm_quit: bool,
+    pending_quit: bool,
+    debug: bool,
+    wx_scan: bool,
+    numa_scan: bool,
+    fast: bool,
+    no_smaps_rollup: bool,
+    numa_selected: usize,
+    numa_expanded: Option<u32>,
+    tree_selected: usize,
+    collapsed: HashSet<u32>,
+    partial: bool,
+    diagnostics: VecDeque<String>,
+    timings: Option<TimingStats>,
+    show_diagnostics: bool,
+    show_help: bool,
+    compact: bool,
+    full_names: bool,
+    watchlist: HashSet<u32>,
+    alert_pss_kb: Option<u64>,
+    alerted: HashSet<u32>,
+    on_alert: Option<String>,
+    alert_hook_fired: HashMap<u32, Instant>,
+    bell: bool,
+    clipboard: Clipboard,
+}
+
+struct PendingKill {
+    pid: u32,
+    name: String,
+    signal: Signal,
+}
+
+struct Baseline {
+    captured_at: u64,
+    pss_kb: HashMap<ProcessKey, u64>,
+}
+
+struct Snapshot {
+    captured_at: u64,
+    state: AnalyzedState,
 }
 
 impl App {
-    pub fn new() -> Self {
+    pub fn new(theme: Palette, columns: Vec<Column>) -> Self {
         Self {
-            state: AnalyzedState {
-                processes: Vec::new(),
-                system: crate::analyzer::SystemStats {
-                    total_kb: 0,
-                    used_kb: 0,
-                    available_kb: 0,
-                    cached_kb: 0,
-                    buffers_kb: 0,
-                    swap_total_kb: 0,
-                    swap_used_kb: 0,
-                    total_process_pss_kb: 0,
-                    total_process_rss_kb: 0,
-                },
-                shared_memory: crate::analyzer::SharedMemoryStats {
-                    total_shared_kb: 0,
-                    total_shared_clean_kb: 0,
-                    total_shared_dirty_kb: 0,
-                    sharing_efficiency: 0.0,
-                },
-                numa_nodes: Vec::new(),
-                memory_map: crate::analyzer::MemoryMap {
-                    kernel_kb: 0,
-                    process_private_kb: 0,
-                    process_shared_kb: 0,
-                    cache_kb: 0,
-                    buffers_kb: 0,
-                    free_kb: 0,
-                    slab_kb: 0,
-                    page_tables_kb: 0,
-                },
-            },
+            theme,
+            columns,
+            unit: Unit::Auto,
+            bar_scale: BarScale::Linear,
+            bold_delta_kb: DEFAULT_BOLD_DELTA_KB,
+            delta_in_kb: false,
+            state: AnalyzedState::default(),
+            processes: Vec::new(),
+            cgroup_filter: None,
+            user_filter: None,
+            name_filter: String::new(),
+            editing_filter: false,
+            hide_kernel_threads: false,
             sort_mode: SortMode::Pss,
+            ascending: false,
             view_mode: ViewMode::Processes,
             scroll_offset: 0,
             visible_rows: 20,
+            table_area: Rect::default(),
+            selected: 0,
+            selected_pid: None,
+            show_detail: false,
+            status: None,
+            started: Instant::now(),
+            last_update: Instant::now(),
+            stale_after: Duration::from_secs(2),
+            failures: 0,
+            paused_at: None,
+            baseline: None,
+            snapshot_a: None,
+            snapshot_b: None,
+            snapshot_diff: Vec::new(),
+            show_baseline: false,
+            pending_kill: None,
+            confirm_quit: false,
+            pending_quit: false,
+            debug: false,
+            wx_scan: false,
+            numa_scan: false,
+            fast: false,
+            no_smaps_rollup: false,
+            numa_selected: 0,
+            numa_expanded: None,
+            tree_selected: 0,
+            collapsed: HashSet::new(),
+            partial: false,
+            diagnostics: VecDeque::new(),
+            timings: None,
+            show_diagnostics: false,
+            show_help: false,
+            compact: false,
+            full_names: false,
+            watchlist: HashSet::new(),
+            alert_pss_kb: None,
+            alerted: HashSet::new(),
+            on_alert: None,
+            alert_hook_fired: HashMap::new(),
+            bell: false,
+            clipboard: Clipboard::default(),
         }
     }
 
-    pub fn update_data(&mut self, mut state: AnalyzedState) {
-        match self.sort_mode {
-            SortMode::Pss => state.processes.sort_by(|a, b| b.pss_kb.cmp(&a.pss_kb)),
-            SortMode::Rss => state.processes.sort_by(|a, b| b.rss_kb.cmp(&a.rss_kb)),
-            SortMode::Shared => state.processes.sort_by(|a, b| b.shared_kb.cmp(&a.shared_kb)),
-            SortMode::Pid => state.processes.sort_by_key(|p| p.pid),
+    // Data counts as stale once two refreshes in a row have been missed.
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.stale_after = interval.saturating_mul(2);
+        self
+    }
+
+    pub fn with_unit(mut self, unit: Unit) -> Self {
+        self.unit = unit;
+        self
+    }
+
+    pub fn next_unit(&mut self) {
+        self.unit = self.unit.next();
+        match self.unit {
+            Unit::Pages => self.set_status(format!(
+                "Units: pages of {} KiB",
+                os_utils::page_size_kb()
+            )),
+            unit => self.set_status(format!("Units: {}", unit.label())),
         }
+    }
 
-        self.state = state;
+    pub fn next_bar_scale(&mut self) {
+        self.bar_scale = self.bar_scale.next();
+        self.set_status(format!("Memory map bars: {}", self.bar_scale.label()));
     }
 
-    pub fn next_sort(&mut self) {
-        self.sort_mode = match self.sort_mode {
-            SortMode::Pss => SortMode::Rss,
-            SortMode::Rss => SortMode::Shared,
-            SortMode::Shared => SortMode::Pid,
-            SortMode::Pid => SortMode::Pss,
-        };
-        self.scroll_offset = 0;
+    pub fn with_delta(mut self, bold_delta_kb: Option<u64>, delta_in_kb: bool) -> Self {
+        self.bold_delta_kb = bold_delta_kb.unwrap_or(DEFAULT_BOLD_DELTA_KB);
+        self.delta_in_kb = delta_in_kb;
+        self
     }
 
-    pub fn toggle_view(&mut self) {
-        self.view_mode = match self.view_mode {
-            ViewMode::Processes => ViewMode::MemoryMap,
-            ViewMode::MemoryMap => ViewMode::SharedMemory,
-            ViewMode::SharedMemory => ViewMode::Processes,
-        };
-        self.scroll_offset = 0;
+    pub fn with_partial(mut self, partial: bool) -> Self {
+        self.partial = partial;
+        self
     }
 
-    pub fn scroll_up(&mut self) {
-        self.scroll_offset = self.scroll_offset.saturating_sub(1);
+    pub fn with_wx_scan(mut self, wx_scan: bool) -> Self {
+        self.wx_scan = wx_scan;
+        self
     }
 
-    pub fn scroll_down(&mut self) {
-        if self.scroll_offset + self.visible_rows < self.state.processes.len() {
-            self.scroll_offset += 1;
+    pub fn with_numa_scan(mut self, numa_scan: bool) -> Self {
+        self.numa_scan = numa_scan;
+        self
+    }
+
+    pub fn with_no_smaps_rollup(mut self, missing: bool) -> Self {
+        self.no_smaps_rollup = missing;
+        self
+    }
+
+    pub fn with_sort(mut self, sort: Option<SortMode>) -> Self {
+        if let Some(mode) = sort {
+            self.sort_mode = mode;
+            self.ascending = mode == SortMode::Pid;
         }
+        self
     }
 
-    pub fn page_up(&mut self) {
-        self.scroll_offset = self.scroll_offset.saturating_sub(self.visible_rows);
+    pub fn with_view(mut self, view: Option<ViewMode>) -> Self {
+        if let Some(view) = view {
+            self.view_mode = view;
+        }
+        self
     }
 
-    pub fn page_down(&mut self) {
-        let max_offset = self.state.processes.len().saturating_sub(self.visible_rows);
-        self.scroll_offset = (self.scroll_offset + self.visible_rows).min(max_offset);
+    pub fn with_hide_kernel_threads(mut self, hide: bool) -> Self {
+        self.hide_kernel_threads = hide;
+        self
     }
-}
 
-pub fn draw(f: &mut Frame, app: &mut App) {
-    let chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([
-            Constraint::Length(7),
-            Constraint::Min(10),
-            Constraint::Length(3),
-        ])
-        .split(f.area());
+    pub fn with_name_filter(mut self, filter: String) -> Self {
+        self.name_filter = filter;
+        self
+    }
 
-    app.visible_rows = chunks[1].height.saturating_sub(3) as usize;
+    // Every PSS is zero without smaps_rollup, so RSS is the useful default.
+    pub fn with_fast(mut self, fast: bool) -> Self {
+        self.fast = fast;
+        if fast {
+            self.sort_mode = SortMode::Rss;
+        }
+        self
+    }
 
-    draw_system_stats(f, chunks[0], app);
+    pub fn with_debug(mut self, debug: bool) -> Self {
+        self.debug = debug;
+        self
+    }
 
-    match app.view_mode {
-        ViewMode::Processes => draw_process_list(f, chunks[1], app),
-        ViewMode::MemoryMap => draw_memory_map(f, chunks[1], app),
-        ViewMode::SharedMemory => draw_shared_view(f, chunks[1], app),
+    pub fn push_warnings(&mut self, warnings: Vec<String>) {
+        let now = clock::time_of_day(clock::unix_now());
+        for warning in warnings {
+            if self.diagnostics.len() == DIAGNOSTICS_LEN {
+                self.diagnostics.pop_front();
+            }
+            self.diagnostics.push_back(format!("{} {}", now, warning));
+        }
     }
 
-    draw_help(f, chunks[2], app);
-}
+    pub fn set_timings(&mut self, timings: TimingStats) {
+        self.timings = Some(timings);
+    }
 
-fn draw_system_stats(f: &mut Frame, area: Rect, app: &App) {
-    let sys = &app.state.system;
+    pub fn toggle_help(&mut self) {
+        self.show_help = !self.show_help;
+    }
 
-    let used_pct = if sys.total_kb > 0 {
-        (sys.used_kb as f64 / sys.total_kb as f64) * 100.0
-    } else {
-        0.0
-    };
+    pub fn is_help_open(&self) -> bool {
+        self.show_help
+    }
 
-    let swap_pct = if sys.swap_total_kb > 0 {
-        (sys.swap_used_kb as f64 / sys.swap_total_kb as f64) * 100.0
-    } else {
-        0.0
-    };
+    // Only reachable with --debug, so the key does nothing otherwise.
+    pub fn toggle_diagnostics(&mut self) {
+        self.show_diagnostics = self.debug && !self.show_diagnostics;
+    }
 
-    let lines = vec![
-        Line::from(vec![
-            Span::styled("Memory: ", Style::default().fg(COLOR_SECONDARY)),
-            Span::raw(format!(
-                "{:.1} / {:.1} GiB ({:.1}%)",
-                sys.used_kb as f64 / 1024.0 / 1024.0,
-                sys.total_kb as f64 / 1024.0 / 1024.0,
-                used_pct
-            )),
-        ]),
-        Line::from(vec![
-            Span::styled("Available: ", Style::default().fg(COLOR_SECONDARY)),
-            Span::raw(format!("{:.1} GiB", sys.available_kb as f64 / 1024.0 / 1024.0)),
-        ]),
-        Line::from(vec![
-            Span::styled("Cache/Buffers: ", Style::default().fg(COLOR_SECONDARY)),
-            Span::raw(format!(
-                "{:.1} GiB",
-                (sys.cached_kb + sys.buffers_kb) as f64 / 1024.0 / 1024.0
-            )),
-        ]),
-        Line::from(vec![
-            Span::styled("Swap: ", Style::default().fg(COLOR_SECONDARY)),
-            Span::raw(format!(
-                "{:.1} / {:.1} GiB ({:.1}%)",
-                sys.swap_used_kb as f64 / 1024.0 / 1024.0,
-                sys.swap_total_kb as f64 / 1024.0 / 1024.0,
-                swap_pct
-            )),
-        ]),
-        Line::from(vec![
-            Span::styled("Process PSS: ", Style::default().fg(COLOR_SECONDARY)),
-            Span::raw(format!(
-                "{:.1} GiB (accurate) | RSS: {:.1} GiB (overcounted)",
-                sys.total_process_pss_kb as f64 / 1024.0 / 1024.0,
-                sys.total_process_rss_kb as f64 / 1024.0 / 1024.0,
-            )),
-        ]),
-    ];
+    pub fn with_confirm_quit(mut self, confirm_quit: bool) -> Self {
+        self.confirm_quit = confirm_quit;
+        self
+    }
 
-    let para = Paragraph::new(lines)
-        .block(Block::default().borders(Borders::ALL).title("System Memory"));
-    f.render_widget(para, area);
-}
+    pub fn with_alert_pss(mut self, alert_pss_mb: Option<u64>) -> Self {
+        self.alert_pss_kb = alert_pss_mb.map(|mb| mb * 1024);
+        self
+    }
 
-fn draw_process_list(f: &mut Frame, area: Rect, app: &App) {
-    let header_cells = ["PID", "Name", "PSS", "RSS", "Shared", "Private", "Swap", "Delta"]
-        .iter()
-        .map(|h| {
-            ratatui::text::Text::from(*h).style(
-                Style::default()
-                    .fg(COLOR_SECONDARY)
-                    .add_modifier(Modifier::BOLD),
-            )
-        });
+    pub fn with_on_alert(mut self, on_alert: Option<String>) -> Self {
+        self.on_alert = on_alert;
+        self
+    }
 
-    let header = Row::new(header_cells).height(1).bottom_margin(1);
+    pub fn update_data(&mut self, mut state: AnalyzedState) {
+        self.sort_mode.sort_processes(&mut state.processes);
 
-    let rows: Vec<Row> = app.state.processes
-        .iter()
-        .skip(app.scroll_offset)
-        .take(app.visible_rows)
-        .map(|proc| {
-            let delta_str = if proc.pss_delta_kb != 0 {
-                format!("{:+}", proc.pss_delta_kb / 1024)
-            } else {
-                String::from("-")
-            };
+        sort_groups(&mut state.groups, self.sort_mode);
+        sort_groups(&mut state.users, self.sort_mode);
 
-            Row::new(vec![
-                proc.pid.to_string(),
-                proc.name.clone(),
-                format!("{} M", proc.pss_kb / 1024),
-                format!("{} M", proc.rss_kb / 1024),
-                format!("{} M", proc.shared_kb / 1024),
-                format!("{} M", proc.private_kb / 1024),
-                format!("{} M", proc.swap_kb / 1024),
-                delta_str,
-            ])
-            .style(if proc.pss_delta_kb.abs() > 10240 {
-                Style::default().add_modifier(Modifier::BOLD)
-            } else {
-                Style::default()
-            })
-        })
-        .collect();
+        // Each mode sorts in its natural direction; flipping reverses it.
+        if self.ascending != (self.sort_mode == SortMode::Pid) {
+            state.processes.reverse();
+            state.groups.reverse();
+            state.users.reverse();
+        }
 
-    let sort_indicator = match app.sort_mode {
-        SortMode::Pss => " [Sort: PSS]",
-        SortMode::Rss => " [Sort: RSS]",
-        SortMode::Shared => " [Sort: Shared]",
-        SortMode::Pid => " [Sort: PID]",
-    };
+        self.watchlist.retain(|pid| state.processes.iter().any(|p| p.pid == *pid));
 
-    let title = format!(
-        "Processes ({}/{}){}",
-        app.scroll_offset.min(app.state.processes.len()),
-        app.state.processes.len(),
-        sort_indicator
-    );
+        if let Some(threshold) = self.alert_pss_kb {
+            let over: HashSet<u32> = state
+                .processes
+                .iter()
+                .filter(|p| alert_kb(p) > threshold)
+                .map(|p| p.pid)
+                .collect();
 
-    let table = Table::new(
-        rows,
-        [
-            Constraint::Length(7),
-            Constraint::Min(20),
-            Constraint::Length(9),
-            Constraint::Length(9),
-            Constraint::Length(9),
-            Constraint::Length(10),
-            Constraint::Length(8),
-            Constraint::Length(8),
-        ],
-    )
-    .header(header)
-    .block(Block::default().borders(Borders::ALL).title(title));
+            // Ring only on the transition; a PID that drops back under the
+            // threshold can alert again.
+            let crossed: Vec<(u32, String)> = state
+                .processes
+                .iter()
+                .filter(|p| over.contains(&p.pid) && !self.alerted.contains(&p.pid))
+                .map(|p| (p.pid, p.name.clone()))
+                .collect();
+            self.bell |= !crossed.is_empty();
+            self.alerted = over;
+            self.run_alert_hook(&crossed);
+        }
 
-    f.render_widget(table, area);
-}
+        self.state = state;
+        self.last_update = Instant::now();
+        self.failures = 0;
+        self.refresh_rows();
+    }
 
-fn draw_memory_map(f: &mut Frame, area: Rect, app: &App) {
-    let map = &app.state.memory_map;
-    let sys = &app.state.system;
+    // A process hovering around the threshold crosses it again and again, so
+    // the hook runs at most once per PID per cooldown.
+    fn run_alert_hook(&mut self, crossed: &[(u32, String)]) {
+        let Some(command) = self.on_alert.clone() else {
+            return;
+        };
 
-    let total = sys.total_kb as f64;
+        self.alert_hook_fired.retain(|_, fired| fired.elapsed() < ALERT_HOOK_COOLDOWN);
+        for (pid, name) in crossed {
+            if self.alert_hook_fired.contains_key(pid) {
+                continue;
+            }
+            self.alert_hook_fired.insert(*pid, Instant::now());
 
-    let mut lines = vec![
-        Line::from(Span::styled(
-            "Physical Memory Distribution:",
-            Style::default().fg(COLOR_SECONDARY).add_modifier(Modifier::BOLD),
-        )),
-        Line::from(""),
-    ];
+            if let Err(err) = os_utils::spawn_detached(&command, &[pid.to_string(), name.clone()]) {
+                self.set_status(format!("Failed to run {}: {}", command, err));
+            }
+        }
+    }
 
-    let items = vec![
-        ("Kernel", map.kernel_kb, COLOR_SECONDARY),
-        ("Process Private", map.process_private_kb, COLOR_PRIMARY),
-        ("Process Shared", map.process_shared_kb, COLOR_PRIMARY),
-        ("Page Cache", map.cache_kb, COLOR_PRIMARY),
-        ("Buffers", map.buffers_kb, COLOR_PRIMARY),
-        ("Slab", map.slab_kb, COLOR_PRIMARY),
-        ("Page Tables", map.page_tables_kb, COLOR_PRIMARY),
-        ("Free", map.free_kb, COLOR_SECONDARY),
-    ];
+    // The last good state stays on screen; the header shows how old it is.
+    pub fn collection_failed(&mut self, message: String, failures: u32) {
+        self.failures = failures;
+        self.set_status(format!("Collection failed: {}", message));
+    }
 
-    for (label, kb, color) in items {
-        let gb = kb as f64 / 1024.0 / 1024.0;
-        let pct = if total > 0.0 {
-            (kb as f64 / total) * 100.0
-        } else {
-            0.0
-        };
+    pub fn watchpoint_failed(&mut self, message: String) {
+        self.set_status(format!("Watchpoint failed: {}", message));
+    }
 
-        let bar_width = ((pct / 100.0) * 50.0) as usize;
-        let bar = "#".repeat(bar_width);
+    fn refresh_rows(&mut self) {
+        self.processes = self
+            .state
+            .processes
+            .iter()
+            .filter(|p| self.cgroup_filter.as_ref().is_none_or(|cg| &p.cgroup == cg))
+            .filter(|p| self.user_filter.as_ref().is_none_or(|(uid, _)| p.uid == *uid))
+            .filter(|p| !(self.hide_kernel_threads && p.is_kernel_thread))
+            .filter(|p| {
+                self.name_filter.is_empty()
+                    || find_match(&process_cell(self, p, Column::Name), &self.name_filter).is_some()
+            })
+            .cloned()
+            .collect();
 
-        lines.push(Line::from(vec![
-            Span::styled(format!("{:16} ", label), Style::default().fg(color)),
-            Span::raw(format!("{:7.1} GiB ({:5.1}%) ", gb, pct)),
-            Span::styled(bar, Style::default().fg(color)),
-        ]));
+        // Stable sort keeps the active sort order within both partitions.
+        self.processes.sort_by_key(|p| !self.watchlist.contains(&p.pid));
+
+        self.restore_selection();
     }
 
-    lines.push(Line::from(""));
-    lines.push(Line::from(vec![
-        Span::styled("Total: ", Style::default().fg(COLOR_SECONDARY)),
-        Span::raw(format!("{:.1} GiB", total / 1024.0 / 1024.0)),
-    ]));
+    pub fn take_bell(&mut self) -> bool {
+        std::mem::take(&mut self.bell)
+    }
 
-    if !app.state.numa_nodes.is_empty() {
-        lines.push(Line::from(""));
-        lines.push(Line::from(Span::styled(
-            "NUMA Nodes:",
-            Style::default().fg(COLOR_SECONDARY).add_modifier(Modifier::BOLD),
-        )));
+    pub fn toggle_watch(&mut self) {
+        if self.view_mode != ViewMode::Processes {
+            return;
+        }
 
-        for node in &app.state.numa_nodes {
-            let used_pct = if node.mem_total_kb > 0 {
-                (node.mem_used_kb as f64 / node.mem_total_kb as f64) * 100.0
-            } else {
-                0.0
-            };
+        let Some(pid) = self.selected_pid else {
+            return;
+        };
 
-            lines.push(Line::from(vec![
-                Span::raw(format!("  Node {}: ", node.node_id)),
-                Span::raw(format!(
-                    "{:.1} / {:.1} GiB ({:.1}%)",
-                    node.mem_used_kb as f64 / 1024.0 / 1024.0,
-                    node.mem_total_kb as f64 / 1024.0 / 1024.0,
-                    used_pct
-                )),
-            ]));
+        if !self.watchlist.remove(&pid) {
+            self.watchlist.insert(pid);
         }
+        self.refresh_rows();
     }
 
-    let para = Paragraph::new(lines)
-        .block(Block::default().borders(Borders::ALL).title("Physical Memory Map"));
-    f.render_widget(para, area);
-}
+    pub fn toggle_compact(&mut self) {
+        self.compact = !self.compact;
+    }
 
-fn draw_shared_view(f: &mut Frame, area: Rect, app: &App) {
-    let shared = &app.state.shared_memory;
+    pub fn toggle_full_names(&mut self) {
+        self.full_names = !self.full_names;
+        self.refresh_rows();
+    }
 
-    let lines = vec![
-        Line::from(vec![
-            Span::styled("Total Shared Memory: ", Style::default().fg(COLOR_SECONDARY)),
-            Span::raw(format!("{:.1} GiB", shared.total_shared_kb as f64 / 1024.0 / 1024.0)),
-        ]),
-        Line::from(vec![
-            Span::styled("  Clean: ", Style::default().fg(COLOR_PRIMARY)),
-            Span::raw(format!("{:.1} GiB", shared.total_shared_clean_kb as f64 / 1024.0 / 1024.0)),
-        ]),
-        Line::from(vec![
-            Span::styled("  Dirty: ", Style::default().fg(COLOR_PRIMARY)),
-            Span::raw(format!("{:.1} GiB", shared.total_shared_dirty_kb as f64 / 1024.0 / 1024.0)),
-        ]),
-        Line::from(vec![
-            Span::styled("Sharing Efficiency: ", Style::default().fg(COLOR_SECONDARY)),
-            Span::raw(format!("{:.1}%", shared.sharing_efficiency)),
-        ]),
-        Line::from(""),
-        Line::from(Span::styled(
-            "Memory saved by sharing pages across processes",
-            Style::default().fg(COLOR_PRIMARY),
-        )),
-    ];
+    pub fn is_editing_filter(&self) -> bool {
+        self.editing_filter
+    }
+
+    pub fn start_filter(&mut self) {
+        self.editing_filter = true;
+    }
+
+    pub fn filter_push(&mut self, c: char) {
+        self.name_filter.push(c);
+        self.refresh_rows();
+    }
+
+    pub fn filter_pop(&mut self) {
+        self.name_filter.pop();
+        self.refresh_rows();
+    }
+
+    pub fn finish_filter(&mut self) {
+        self.editing_filter = false;
+    }
+
+    pub fn cancel_filter(&mut self) {
+        self.editing_filter = false;
+        self.name_filter.clear();
+        self.refresh_rows();
+    }
+
+    pub fn toggle_kernel_threads(&mut self) {
+        self.hide_kernel_threads = !self.hide_kernel_threads;
+        self.set_status(if self.hide_kernel_threads {
+            String::from("Kernel threads hidden")
+        } else {
+            String::from("Kernel threads shown")
+        });
+        self.refresh_rows();
+    }
+
+    pub fn toggle_cgroup_filter(&mut self) {
+        self.cgroup_filter = match self.cgroup_filter {
+            Some(_) => None,
+            None => self.selected_process().map(|p| p.cgroup.clone()),
+        };
+        self.refresh_rows();
+    }
+
+    pub fn toggle_user_filter(&mut self) {
+        self.user_filter = match self.user_filter {
+            Some(_) => None,
+            None => self.selected_process().map(|p| (p.uid, p.username.clone())),
+        };
+        self.refresh_rows();
+    }
+
+    fn restore_selection(&mut self) {
+        let found = self
+            .selected_pid
+            .and_then(|pid| self.processes.iter().position(|p| p.pid == pid));
+
+        match found {
+            Some(idx) => self.select(idx),
+            None => {
+                self.show_detail = false;
+                self.select(self.selected);
+            }
+        }
+    }
+
+    fn select(&mut self, idx: usize) {
+        self.selected = idx.min(self.processes.len().saturating_sub(1));
+        self.selected_pid = self.processes.get(self.selected).map(|p| p.pid);
+
+        let rows = self.visible_rows.max(1);
+        if self.selected < self.scroll_offset {
+            self.scroll_offset = self.selected;
+        } else if self.selected >= self.scroll_offset + rows {
+            self.scroll_offset = self.selected + 1 - rows;
+        }
+        self.scroll_offset = self.scroll_offset.min(self.processes.len().saturating_sub(rows));
+    }
+
+    fn wx_processes(&self) -> Vec<&ProcessStats> {
+        let mut procs: Vec<&ProcessStats> =
+            self.state.processes.iter().filter(|p| p.wx_regions > 0).collect();
+        procs.sort_by_key(|p| (std::cmp::Reverse(p.wx_regions), p.pid));
+        procs
+    }
+
+    fn numa_consumers(&self, node_id: u32) -> Vec<(&ProcessStats, u64)> {
+        let mut procs: Vec<(&ProcessStats, u64)> = self
+            .state
+            .processes
+            .iter()
+            .filter_map(|p| p.numa_kb.get(&node_id).map(|&kb| (p, kb)))
+            .filter(|&(_, kb)| kb > 0)
+            .collect();
+        procs.sort_by_key(|&(p, kb)| (std::cmp::Reverse(kb), p.pid));
+        procs
+    }
+
+    fn toggle_numa_node(&mut self) {
+        let Some(node) = self.state.numa_nodes.get(self.numa_selected) else {
+            return;
+        };
+
+        self.numa_expanded = match self.numa_expanded {
+            Some(id) if id == node.node_id => None,
+            _ => Some(node.node_id),
+        };
+    }
+
+    // The tree minus the subtrees under collapsed rows and, when hidden,
+    // kernel threads. Collapsed PIDs stay collapsed until they exit.
+    fn tree_rows(&self) -> Vec<&TreeRow> {
+        let kernel_threads: HashSet<u32> = self
+            .state
+            .processes
+            .iter()
+            .filter(|p| self.hide_kernel_threads && p.is_kernel_thread)
+            .map(|p| p.pid)
+            .collect();
+
+        let mut rows = Vec::with_capacity(self.state.tree.len());
+        let mut hidden_below = None;
+        for row in &self.state.tree {
+            match hidden_below {
+                Some(depth) if row.depth > depth => continue,
+                _ => hidden_below = None,
+            }
+            if kernel_threads.contains(&row.pid) {
+                continue;
+            }
+            if self.collapsed.contains(&row.pid) {
+                hidden_below = Some(row.depth);
+            }
+            rows.push(row);
+        }
+        rows
+    }
+
+    fn toggle_tree_node(&mut self) {
+        let Some((pid, descendants)) = self
+            .tree_rows()
+            .get(self.tree_selected)
+            .map(|row| (row.pid, row.descendants))
+        else {
+            return;
+        };
+        if descendants > 0 && !self.collapsed.remove(&pid) {
+            self.collapsed.insert(pid);
+        }
+    }
+
+    fn selected_process(&self) -> Option<&ProcessStats> {
+        self.processes.get(self.selected)
+    }
+
+    pub fn open_detail(&mut self) {
+        match self.view_mode {
+            ViewMode::Processes if self.selected_process().is_some() => self.show_detail = true,
+            ViewMode::Numa => self.toggle_numa_node(),
+            ViewMode::Tree => self.toggle_tree_node(),
+            _ => {}
+        }
+    }
+
+    pub fn close_detail(&mut self) {
+        self.show_detail = false;
+        self.numa_expanded = None;
+    }
+
+    pub fn toggle_pause(&mut self) -> bool {
+        self.paused_at = match self.paused_at {
+            Some(_) => None,
+            None => Some(clock::unix_now()),
+        };
+        self.is_paused()
+    }
+
+    // A manual refresh while paused moves the frozen frame forward, so the
+    // "paused at" time follows the data on screen.
+    pub fn refreshed_manually(&mut self) {
+        if let Some(at) = &mut self.paused_at {
+            *at = clock::unix_now();
+        }
+    }
+
+    pub fn finish_replay(&mut self, message: String) {
+        self.paused_at.get_or_insert_with(clock::unix_now);
+        self.set_status(message);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused_at.is_some()
+    }
+
+    pub fn capture_baseline(&mut self) {
+        self.baseline = Some(Baseline {
+            captured_at: clock::unix_now(),
+            pss_kb: self
+                .state
+                .processes
+                .iter()
+                .map(|p| ((p.pid, p.start_time_ticks), p.pss_kb))
+                .collect(),
+        });
+        self.show_baseline = true;
+        self.set_status(format!(
+            "Captured baseline of {} processes",
+            self.state.processes.len()
+        ));
+    }
+
+    // Alternates between capturing A and capturing B; capturing B opens the
+    // diff view, and the next press starts over with a new A.
+    pub fn capture_snapshot(&mut self) {
+        let snapshot = Snapshot {
+            captured_at: clock::unix_now(),
+            state: self.state.clone(),
+        };
+
+        match &self.snapshot_a {
+            Some(a) if self.snapshot_b.is_none() => {
+                self.snapshot_diff =
+                    analyzer::diff_processes(&a.state.processes, &snapshot.state.processes);
+                self.snapshot_b = Some(snapshot);
+                self.set_status(format!(
+                    "Captured snapshot B: {} processes changed since A",
+                    self.snapshot_diff.len()
+                ));
+                self.set_view(ViewMode::Diff);
+            }
+            _ => {
+                self.snapshot_a = Some(snapshot);
+                self.snapshot_b = None;
+                self.snapshot_diff.clear();
+                if self.view_mode == ViewMode::Diff {
+                    self.set_view(ViewMode::Processes);
+                }
+                self.set_status(String::from("Captured snapshot A, press a again to capture B"));
+            }
+        }
+    }
+
+    // Without a clipboard (no feature, no display) the PID still lands in
+    // the status line, where it can be read off and typed.
+    pub fn copy_selected_pid(&mut self) {
+        if self.view_mode != ViewMode::Processes {
+            return;
+        }
+        let Some(proc) = self.selected_process() else {
+            return;
+        };
+
+        let pid = proc.pid.to_string();
+        let label = format!("PID {} ({})", proc.pid, proc.name);
+        let message = match self.clipboard.copy(&pid) {
+            Ok(()) => format!("Copied {} to clipboard", label),
+            Err(_) => format!("{}, clipboard unavailable", label),
+        };
+        self.set_status(message);
+    }
+
+    pub fn toggle_baseline(&mut self) {
+        if self.baseline.is_some() {
+            self.show_baseline = !self.show_baseline;
+        } else {
+            self.set_status(String::from("No baseline captured yet, press b"));
+        }
+    }
+
+    fn active_baseline(&self) -> Option<&Baseline> {
+        self.baseline.as_ref().filter(|_| self.show_baseline)
+    }
+
+    pub fn request_kill(&mut self, signal: Signal) {
+        if self.view_mode != ViewMode::Processes {
+            return;
+        }
+
+        match self.selected_process() {
+            Some(proc) if proc.pid > 1 => {
+                self.pending_kill = Some(PendingKill {
+                    pid: proc.pid,
+                    name: proc.name.clone(),
+                    signal,
+                });
+            }
+            Some(proc) => self.set_status(format!("Refusing to signal PID {}", proc.pid)),
+            None => {}
+        }
+    }
+
+    pub fn has_pending_kill(&self) -> bool {
+        self.pending_kill.is_some()
+    }
+
+    pub fn confirm_kill(&mut self) {
+        let Some(kill) = self.pending_kill.take() else {
+            return;
+        };
+
+        let message = match os_utils::send_signal(kill.pid, kill.signal) {
+            Ok(()) => format!("Sent {} to {} ({})", kill.signal.name(), kill.pid, kill.name),
+            Err(err) => format!(
+                "Failed to send {} to {}: {}",
+                kill.signal.name(),
+                kill.pid,
+                err
+            ),
+        };
+        self.set_status(message);
+    }
+
+    pub fn cancel_kill(&mut self) {
+        self.pending_kill = None;
+    }
+
+    // Returns true when the caller should exit now; with --confirm-quit the
+    // first q only opens the prompt.
+    pub fn request_quit(&mut self) -> bool {
+        if !self.confirm_quit {
+            return true;
+        }
+
+        self.pending_quit = true;
+        false
+    }
+
+    pub fn has_pending_quit(&self) -> bool {
+        self.pending_quit
+    }
+
+    pub fn cancel_quit(&mut self) {
+        self.pending_quit = false;
+    }
+
+    pub fn export_csv(&mut self) {
+        let message = match export::write_process_csv(&self.processes) {
+            Ok(path) => format!("Exported {} processes to {}", self.processes.len(), path.display()),
+            Err(err) => format!("Export failed: {:#}", err),
+        };
+        self.set_status(message);
+    }
+
+    // Views that need --wx-scan, --numa-scan or snapshots can't be started
+    // in, so the file keeps its previous view for those.
+    pub fn save_preferences(&mut self) {
+        let startup_view = ViewMode::by_name(self.view_mode.name()).is_ok();
+        let message = match config::save(|config| {
+            config.theme = Some(self.theme.name.to_string());
+            config.sort = Some(self.sort_mode.name().to_string());
+            if startup_view {
+                config.view = Some(self.view_mode.name().to_string());
+            }
+            config.units = Some(self.unit.name().to_string());
+            config.hide_kernel_threads = Some(self.hide_kernel_threads);
+            config.filter = Some(self.name_filter.clone()).filter(|f| !f.is_empty());
+        }) {
+            Ok(path) => format!("Saved preferences to {}", path.display()),
+            Err(err) => format!("Saving preferences failed: {:#}", err),
+        };
+        self.set_status(message);
+    }
+
+    pub fn export_report(&mut self) {
+        let message = match report::write_report(&self.state) {
+            Ok(path) => format!("Wrote report to {}", path.display()),
+            Err(err) => format!("Report failed: {:#}", err),
+        };
+        self.set_status(message);
+    }
+
+    fn set_status(&mut self, message: String) {
+        self.status = Some((message, Instant::now()));
+    }
+
+    fn current_status(&self) -> Option<&str> {
+        self.status
+            .as_ref()
+            .filter(|(_, at)| at.elapsed() < STATUS_TIMEOUT)
+            .map(|(message, _)| message.as_str())
+    }
+
+    pub fn next_sort(&mut self) {
+        self.sort_mode = match self.sort_mode {
+            SortMode::Pss => SortMode::Rss,
+            SortMode::Rss => SortMode::Shared,
+            SortMode::Shared => SortMode::Swap,
+            SortMode::Swap => SortMode::PrivateDirty,
+            SortMode::PrivateDirty => SortMode::Pid,
+            SortMode::Pid => SortMode::OomScore,
+            SortMode::OomScore => SortMode::Threads,
+            SortMode::Threads => SortMode::Growth,
+            SortMode::Growth => SortMode::Age,
+            SortMode::Age => SortMode::Thp,
+            SortMode::Thp => SortMode::Pss,
+        };
+        self.ascending = self.sort_mode == SortMode::Pid;
+        self.scroll_offset = 0;
+    }
+
+    pub fn flip_sort(&mut self) {
+        self.ascending = !self.ascending;
+        self.scroll_offset = 0;
+    }
+
+    // Security and NUMA only get a tab when their scan is enabled, the diff
+    // once both snapshots are captured.
+    fn views(&self) -> Vec<ViewMode> {
+        let mut views = ViewMode::STARTUP.to_vec();
+        if self.wx_scan {
+            views.push(ViewMode::Security);
+        }
+        if self.numa_scan {
+            views.push(ViewMode::Numa);
+        }
+        if self.snapshot_b.is_some() {
+            views.push(ViewMode::Diff);
+        }
+        views
+    }
+
+    fn view_index(&self, views: &[ViewMode]) -> usize {
+        views.iter().position(|&v| v == self.view_mode).unwrap_or(0)
+    }
+
+    fn next_view(&self) -> ViewMode {
+        let views = self.views();
+        views[(self.view_index(&views) + 1) % views.len()]
+    }
+
+    fn prev_view(&self) -> ViewMode {
+        let views = self.views();
+        views[(self.view_index(&views) + views.len() - 1) % views.len()]
+    }
+
+    // Tabs are numbered from 1 on screen; a number without a tab does nothing.
+    pub fn select_view(&mut self, number: usize) {
+        if let Some(&view) = self.views().get(number.wrapping_sub(1)) {
+            self.set_view(view);
+        }
+    }
+
+    pub fn toggle_view(&mut self) {
+        self.set_view(self.next_view());
+    }
+
+    pub fn toggle_view_back(&mut self) {
+        self.set_view(self.prev_view());
+    }
+
+    fn set_view(&mut self, view_mode: ViewMode) {
+        self.view_mode = view_mode;
+        self.scroll_offset = 0;
+        self.select(self.selected);
+    }
+
+    fn move_by(&mut self, delta: isize) {
+        match self.view_mode {
+            ViewMode::Grouped | ViewMode::Users | ViewMode::Security | ViewMode::Diff => {
+                let len = match self.view_mode {
+                    ViewMode::Users => self.state.users.len(),
+                    ViewMode::Security => self.wx_processes().len(),
+                    ViewMode::Diff => self.snapshot_diff.len(),
+                    _ => self.state.groups.len(),
+                };
+                let max_offset = len.saturating_sub(self.visible_rows);
+                self.scroll_offset = self.scroll_offset.saturating_add_signed(delta).min(max_offset);
+            }
+            ViewMode::Numa => {
+                let last = self.state.numa_nodes.len().saturating_sub(1);
+                self.numa_selected = self.numa_selected.saturating_add_signed(delta).min(last);
+            }
+            ViewMode::Tree => {
+                let len = self.tree_rows().len();
+                let rows = self.visible_rows.max(1);
+                self.tree_selected =
+                    self.tree_selected.saturating_add_signed(delta).min(len.saturating_sub(1));
+                if self.tree_selected < self.scroll_offset {
+                    self.scroll_offset = self.tree_selected;
+                } else if self.tree_selected >= self.scroll_offset + rows {
+                    self.scroll_offset = self.tree_selected + 1 - rows;
+                }
+                self.scroll_offset = self.scroll_offset.min(len.saturating_sub(rows));
+            }
+            _ => self.select(self.selected.saturating_add_signed(delta)),
+        }
+    }
+
+    pub fn scroll_up(&mut self) {
+        self.move_by(-1);
+    }
+
+    pub fn scroll_down(&mut self) {
+        self.move_by(1);
+    }
+
+    // Rows start below the top border, the header line and its bottom margin.
+    pub fn click(&mut self, column: u16, row: u16) {
+        let area = self.table_area;
+        let first_row = area.y + 3;
+
+        if self.view_mode != ViewMode::Processes
+            || self.show_detail
+            || column <= area.x
+            || column >= area.right().saturating_sub(1)
+            || row < first_row
+            || row >= area.bottom().saturating_sub(1)
+        {
+            return;
+        }
+
+        let idx = self.scroll_offset + (row - first_row) as usize;
+        if idx < self.processes.len() {
+            self.select(idx);
+        }
+    }
+
+    pub fn page_up(&mut self) {
+        self.move_by(-(self.visible_rows as isize));
+    }
+
+    pub fn page_down(&mut self) {
+        self.move_by(self.visible_rows as isize);
+    }
+
+    // move_by saturates and clamps to the last row or page, so the extremes
+    // land exactly on the first and last entries of the filtered list.
+    pub fn scroll_top(&mut self) {
+        self.move_by(isize::MIN);
+    }
+
+    pub fn scroll_bottom(&mut self) {
+        self.move_by(isize::MAX);
+    }
+}
+
+pub fn draw(f: &mut Frame, app: &mut App) {
+    // Compact mode gives everything but one header and one help line to the
+    // view, for 80x24 terminals and tmux splits, so it has no tab bar.
+    let stats = if app.compact { Vec::new() } else { system_stats_lines(app) };
+    let constraints = if app.compact {
+        [
+            Constraint::Length(1),
+            Constraint::Length(0),
+            Constraint::Min(4),
+            Constraint::Length(1),
+        ]
+    } else {
+        [
+            Constraint::Length(stats.len() as u16 + 4),
+            Constraint::Length(1),
+            Constraint::Min(10),
+            Constraint::Length(3),
+        ]
+    };
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(constraints)
+        .split(f.area());
+    let chunks = [rows[0], rows[2], rows[3]];
+
+    if !app.compact {
+        draw_tabs(f, rows[1], app);
+    }
+
+    let footer = if app.view_mode == ViewMode::Processes { TOTAL_ROW_LINES } else { 0 };
+    app.visible_rows = chunks[1].height.saturating_sub(3 + footer) as usize;
+    app.table_area = chunks[1];
+    // The row count changes with the terminal size, so the offset is brought
+    // back in range here rather than on the next scroll key.
+    app.move_by(0);
+
+    if app.compact {
+        draw_compact_stats(f, chunks[0], app);
+    } else {
+        draw_system_stats(f, chunks[0], app, stats);
+    }
+
+    match app.view_mode {
+        ViewMode::Processes => draw_process_list(f, chunks[1], app),
+        ViewMode::Grouped => {
+            draw_grouped_list(f, chunks[1], app, &app.state.groups, "Process Groups", "Name")
+        }
+        ViewMode::Users => draw_grouped_list(f, chunks[1], app, &app.state.users, "Users", "User"),
+        ViewMode::MemoryMap => {
+            let parts = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(0), Constraint::Length(12)])
+                .split(chunks[1]);
+            let charts = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Percentage(65), Constraint::Percentage(35)])
+                .split(parts[1]);
+            draw_memory_map(f, parts[0], app);
+            draw_usage_chart(f, charts[0], app);
+            draw_process_count_chart(f, charts[1], app);
+        }
+        ViewMode::SharedMemory => draw_shared_view(f, chunks[1], app),
+        ViewMode::Tree => draw_tree_view(f, chunks[1], app),
+        ViewMode::Security => draw_security_view(f, chunks[1], app),
+        ViewMode::Numa => draw_numa_view(f, chunks[1], app),
+        ViewMode::Diff => draw_diff_view(f, chunks[1], app),
+    }
+
+    draw_help(f, chunks[2], app);
+
+    if app.show_detail {
+        draw_process_detail(f, app);
+    }
+
+    if let Some(kill) = &app.pending_kill {
+        draw_kill_confirm(f, app, kill);
+    }
+
+    if app.show_diagnostics {
+        draw_diagnostics(f, app);
+    }
+
+    if app.show_help {
+        draw_help_overlay(f, app);
+    }
+
+    if app.pending_quit {
+        draw_quit_confirm(f, app);
+    }
+}
+
+fn draw_tabs(f: &mut Frame, area: Rect, app: &App) {
+    let views = app.views();
+    let titles = views
+        .iter()
+        .enumerate()
+        .map(|(i, view)| format!("{} {}", i + 1, view.title()));
+
+    let tabs = Tabs::new(titles)
+        .select(app.view_index(&views))
+        .highlight_style(
+            Style::default()
+                .fg(app.theme.secondary)
+                .add_modifier(Modifier::BOLD | Modifier::REVERSED),
+        );
+    f.render_widget(tabs, area);
+}
+
+fn draw_diagnostics(f: &mut Frame, app: &App) {
+    let area = centered_rect(80, 20, f.area());
+    f.render_widget(Clear, area);
+
+    let mut lines = Vec::new();
+    if let Some(timings) = &app.timings {
+        lines.push(Line::from(format!(
+            "Last {} ticks, interval {:.0} ms:",
+            timings.samples,
+            timings.interval.as_secs_f64() * 1000.0
+        )));
+        for (label, spread) in [("Collect", timings.collect), ("Analyze", timings.analyze)] {
+            let max_pct = spread.max.as_secs_f64() / timings.interval.as_secs_f64() * 100.0;
+            let style = Style::default().fg(app.theme.level(max_pct, 50.0, 100.0));
+            lines.push(Line::from(Span::styled(
+                format!(
+                    "  {}  min {}  avg {}  max {}  ({:.0}% of interval)",
+                    label,
+                    fmt_ms(spread.min),
+                    fmt_ms(spread.avg),
+                    fmt_ms(spread.max),
+                    spread.avg.as_secs_f64() / timings.interval.as_secs_f64() * 100.0
+                ),
+                style,
+            )));
+        }
+        lines.push(Line::from(""));
+    }
+
+    if app.diagnostics.is_empty() {
+        lines.push(Line::from("No parse anomalies so far"));
+    } else {
+        // Newest last, keeping the tail visible when the buffer overflows.
+        let visible = (area.height.saturating_sub(2) as usize).saturating_sub(lines.len());
+        lines.extend(
+            app.diagnostics
+                .iter()
+                .skip(app.diagnostics.len().saturating_sub(visible))
+                .map(|d| Line::from(d.as_str())),
+        );
+    }
+
+    let para = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!("Diagnostics ({}, D to close)", app.diagnostics.len())),
+    );
+    f.render_widget(para, area);
+}
+
+fn fmt_ms(duration: Duration) -> String {
+    format!("{:.1} ms", duration.as_secs_f64() * 1000.0)
+}
+
+const KEY_HELP: &[(&str, &str)] = &[
+    ("q", "quit"),
+    ("?", "show/hide this help"),
+    ("p", "pause/resume"),
+    ("space / F5", "refresh now"),
+    ("n / o", "next sort mode / reverse sort"),
+    ("v / V", "next / previous view"),
+    ("right / left", "next / previous view"),
+    ("1-9", "jump to view tab"),
+    ("up / down", "move selection"),
+    ("PgUp / PgDn", "move one page"),
+    ("Home / End, g / G", "first / last row"),
+    ("Enter / Esc", "open / close details"),
+    ("Enter (Tree)", "collapse / expand subtree"),
+    ("Enter (NUMA)", "expand / collapse node"),
+    ("/", "filter by name (Esc clears)"),
+    ("f / U", "filter to cgroup / user"),
+    ("t", "hide/show kernel threads"),
+    ("w", "watch / unwatch process"),
+    ("y", "copy PID to clipboard"),
+    ("b / B", "capture / toggle baseline"),
+    ("a", "capture snapshot A, then B"),
+    ("k / K", "SIGTERM / SIGKILL process"),
+    ("u", "cycle units"),
+    ("l", "memory map bar scale"),
+    ("x", "names / full command lines"),
+    ("c", "compact layout"),
+    ("e / r", "export CSV / write report"),
+    ("S", "save preferences to config"),
+    ("D", "diagnostics (--debug)"),
+];
+
+fn draw_help_overlay(f: &mut Frame, app: &App) {
+    let height = KEY_HELP.len() as u16 + 2;
+    let area = centered_rect(80, height, f.area());
+    f.render_widget(Clear, area);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title("Help (any key to close)");
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Length(48), Constraint::Min(0)])
+        .split(inner);
+
+    let key_style = Style::default().fg(app.theme.secondary);
+    let keys: Vec<Line> = KEY_HELP
+        .iter()
+        .map(|(key, action)| {
+            Line::from(vec![
+                Span::styled(format!("{:18}", key), key_style),
+                Span::raw(*action),
+            ])
+        })
+        .collect();
+    f.render_widget(Paragraph::new(keys), columns[0]);
+
+    let sample = |text: &str, style: Style, meaning: String| {
+        Line::from(vec![
+            Span::styled(text.to_string(), style),
+            Span::raw(format!("{:width$}{}", "", meaning, width = 10 - text.len())),
+        ])
+    };
+    let legend = vec![
+        Line::from(Span::styled(
+            "Process rows",
+            key_style.add_modifier(Modifier::BOLD),
+        )),
+        sample(
+            "bold",
+            Style::default().add_modifier(Modifier::BOLD),
+            format!("PSS moved over {} in a tick", fmt_kb(app.bold_delta_kb, app.unit)),
+        ),
+        sample(
+            "leak",
+            Style::default().fg(app.theme.leak),
+            String::from("suspected leak (steady growth)"),
+        ),
+        sample(
+            "alert",
+            Style::default().fg(app.theme.alert).add_modifier(Modifier::BOLD),
+            String::from("over the --alert-pss threshold"),
+        ),
+        sample(
+            "dim",
+            Style::default().add_modifier(Modifier::DIM),
+            String::from("kernel thread"),
+        ),
+        sample(
+            "reversed",
+            Style::default().add_modifier(Modifier::REVERSED),
+            String::from("selected row"),
+        ),
+        sample("*", Style::default(), String::from("watched, pinned to the top")),
+        sample(
+            "+1.2M",
+            Style::default().fg(app.theme.grow),
+            String::from("Delta: grew"),
+        ),
+        sample(
+            "-1.2M",
+            Style::default().fg(app.theme.shrink),
+            String::from("Delta: shrank"),
+        ),
+        sample(
+            "match",
+            Style::default()
+                .fg(app.theme.warn)
+                .add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            String::from("part of the name matching /"),
+        ),
+        Line::from(""),
+        Line::from(Span::styled(
+            "Gauges and charts",
+            key_style.add_modifier(Modifier::BOLD),
+        )),
+        sample(
+            "ok",
+            Style::default().fg(app.theme.ok),
+            format!("below {:.0}% used", USAGE_WARN_PCT),
+        ),
+        sample(
+            "warn",
+            Style::default().fg(app.theme.warn),
+            format!("{:.0}% to {:.0}%", USAGE_WARN_PCT, USAGE_CRITICAL_PCT),
+        ),
+        sample(
+            "critical",
+            Style::default().fg(app.theme.critical),
+            format!("{:.0}% and above", USAGE_CRITICAL_PCT),
+        ),
+    ];
+    f.render_widget(Paragraph::new(legend), columns[1]);
+}
+
+fn draw_quit_confirm(f: &mut Frame, app: &App) {
+    let area = centered_rect(40, 5, f.area());
+    f.render_widget(Clear, area);
+
+    let lines = vec![
+        Line::from("Really quit?"),
+        Line::from(""),
+        Line::from(Span::styled(
+            "y: quit | any other key: cancel",
+            Style::default().fg(app.theme.secondary),
+        )),
+    ];
+
+    let para = Paragraph::new(lines)
+        .block(Block::default().borders(Borders::ALL).title("Quit"));
+    f.render_widget(para, area);
+}
+
+fn draw_kill_confirm(f: &mut Frame, app: &App, kill: &PendingKill) {
+    let area = centered_rect(50, 5, f.area());
+    f.render_widget(Clear, area);
+
+    let lines = vec![
+        Line::from(vec![
+            Span::raw("Send "),
+            Span::styled(
+                kill.signal.name(),
+                Style::default().fg(app.theme.error).add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(format!(" to PID {} ({})?", kill.pid, kill.name)),
+        ]),
+        Line::from(""),
+        Line::from(Span::styled(
+            "y: confirm | any other key: cancel",
+            Style::default().fg(app.theme.secondary),
+        )),
+    ];
+
+    let para = Paragraph::new(lines)
+        .block(Block::default().borders(Borders::ALL).title("Confirm"));
+    f.render_widget(para, area);
+}
+
+fn system_stats_lines(app: &App) -> Vec<Line<'static>> {
+    let sys = &app.state.system;
+
+    let used_pct = if sys.total_kb > 0 {
+        (sys.used_kb as f64 / sys.total_kb as f64) * 100.0
+    } else {
+        0.0
+    };
+
+    let swap_pct = if sys.swap_total_kb > 0 {
+        (sys.swap_used_kb as f64 / sys.swap_total_kb as f64) * 100.0
+    } else {
+        0.0
+    };
+
+    let mut lines = vec![
+        Line::from(vec![
+            Span::styled("Memory: ", Style::default().fg(app.theme.secondary)),
+            Span::raw(format!(
+                "{} / {} ({:.1}%)",
+                fmt_kb(sys.used_kb, app.unit),
+                fmt_kb(sys.total_kb, app.unit),
+                used_pct
+            )),
+        ]),
+        Line::from(vec![
+            Span::styled("Available: ", Style::default().fg(app.theme.secondary)),
+            Span::raw(format!(
+                "{} (free + reclaimable) | Free: {} (unused)",
+                fmt_kb(sys.available_kb, app.unit),
+                fmt_kb(sys.free_kb, app.unit)
+            )),
+        ]),
+        Line::from(vec![
+            Span::styled("Cache/Buffers: ", Style::default().fg(app.theme.secondary)),
+            Span::raw(fmt_kb(sys.cached_kb + sys.buffers_kb, app.unit)),
+        ]),
+        Line::from(vec![
+            Span::styled("Swap: ", Style::default().fg(app.theme.secondary)),
+            Span::raw(format!(
+                "{} / {} ({:.1}%) | Process SwapPss: {}",
+                fmt_kb(sys.swap_used_kb, app.unit),
+                fmt_kb(sys.swap_total_kb, app.unit),
+                swap_pct,
+                fmt_kb(sys.total_process_swap_pss_kb, app.unit),
+            )),
+        ]),
+    ];
+
+    if let Some(commit) = &sys.commit {
+        let style = if commit.exceeded() {
+            Style::default().fg(app.theme.error).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+        };
+        let mode = match commit.overcommit_memory {
+            Some(0) => " | overcommit: heuristic",
+            Some(1) => " | overcommit: always",
+            Some(2) => " | overcommit: never",
+            _ => "",
+        };
+
+        lines.push(Line::from(vec![
+            Span::styled("Commit: ", Style::default().fg(app.theme.secondary)),
+            Span::styled(
+                format!(
+                    "{} / {} limit ({:.0}%){}",
+                    fmt_kb(commit.committed_kb, app.unit),
+                    fmt_kb(commit.limit_kb, app.unit),
+                    commit.ratio_pct,
+                    if commit.exceeded() { " OVERCOMMITTED" } else { "" }
+                ),
+                style,
+            ),
+            Span::raw(mode),
+        ]));
+    }
+
+    // Sustained swap-out is the clearest sign of memory pressure.
+    if let Some(rates) = &sys.swap_rates {
+        let out_style = if rates.swap_out_per_s > 0.0 {
+            Style::default().fg(app.theme.warn).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+        };
+
+        lines.push(Line::from(vec![
+            Span::styled("Swap I/O: ", Style::default().fg(app.theme.secondary)),
+            Span::raw(format!("in {:.0} pages/s | ", rates.swap_in_per_s)),
+            Span::styled(format!("out {:.0} pages/s", rates.swap_out_per_s), out_style),
+            Span::raw(format!(" | major faults {:.0}/s", rates.major_faults_per_s)),
+        ]));
+    }
+
+    if let Some(zswap) = &sys.zswap {
+        let ratio = if zswap.pool_kb > 0 {
+            format!("{:.1}x", zswap.stored_kb as f64 / zswap.pool_kb as f64)
+        } else {
+            String::from("-")
+        };
+
+        lines.push(Line::from(vec![
+            Span::styled("Zswap: ", Style::default().fg(app.theme.secondary)),
+            Span::raw(format!(
+                "{} stored in {} compressed (ratio {})",
+                fmt_kb(zswap.stored_kb, app.unit),
+                fmt_kb(zswap.pool_kb, app.unit),
+                ratio,
+            )),
+        ]));
+    }
+
+    lines.push(Line::from(vec![
+        Span::styled("Process PSS: ", Style::default().fg(app.theme.secondary)),
+        Span::raw(format!(
+            "{} (accurate) | RSS: {} (overcounted)",
+            fmt_kb(sys.total_process_pss_kb, app.unit),
+            fmt_kb(sys.total_process_rss_kb, app.unit),
+        )),
+    ]));
+
+    lines.push(Line::from(vec![
+        Span::styled("Anon/File: ", Style::default().fg(app.theme.secondary)),
+        Span::raw(format!(
+            "{} anonymous (swappable) | {} file-backed (reclaimable) across processes",
+            fmt_kb(sys.total_process_anon_kb, app.unit),
+            fmt_kb(sys.total_process_file_kb, app.unit),
+        )),
+    ]));
+
+    if let Some(psi) = &sys.pressure {
+        lines.push(Line::from(vec![
+            Span::styled("Pressure: ", Style::default().fg(app.theme.secondary)),
+            Span::raw(format!(
+                "some {:.2} / {:.2} / {:.2} | full {:.2} / {:.2} / {:.2} (avg10/60/300 %)",
+                psi.some_avg10,
+                psi.some_avg60,
+                psi.some_avg300,
+                psi.full_avg10,
+                psi.full_avg60,
+                psi.full_avg300,
+            )),
+        ]));
+    }
+
+    lines
+}
+
+fn draw_system_stats(f: &mut Frame, area: Rect, app: &App, lines: Vec<Line<'static>>) {
+    let mut title = match app.paused_at {
+        Some(at) => format!("System Memory (paused at {})", clock::time_of_day(at)),
+        None => String::from("System Memory"),
+    };
+
+    // Even root can be denied a few PIDs (e.g. a protected init), so the
+    // banner is reserved for --no-root where the gaps are expected and large.
+    if app.partial && app.state.processes_unreadable > 0 {
+        title.push_str(&format!(
+            " [partial data: {} processes unreadable]",
+            app.state.processes_unreadable
+        ));
+    }
+
+    let sys = &app.state.system;
+    if sys.cgroup_limited {
+        title.push_str(&format!(
+            " [limited by cgroup: {} of {} host]",
+            fmt_kb(sys.total_kb, app.unit),
+            fmt_kb(sys.host_total_kb, app.unit)
+        ));
+    }
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(title)
+        .title_top(session_line(app).right_aligned());
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(1), Constraint::Length(1)])
+        .split(inner);
+
+    f.render_widget(Paragraph::new(lines), rows[0]);
+    f.render_widget(Paragraph::new(memory_meter(app, rows[1].width)), rows[1]);
+
+    let gauges = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .spacing(1)
+        .split(rows[2]);
+
+    let items = [
+        ("Memory", sys.used_kb, sys.total_kb),
+        ("Swap", sys.swap_used_kb, sys.swap_total_kb),
+    ];
+
+    for ((label, used, total), area) in items.into_iter().zip(gauges.iter()) {
+        let pct = percent(used, total);
+        let gauge = Gauge::default()
+            .gauge_style(
+                Style::default().fg(app.theme.level(pct, USAGE_WARN_PCT, USAGE_CRITICAL_PCT)),
+            )
+            .ratio((pct / 100.0).clamp(0.0, 1.0))
+            .label(format!("{} {:.1}%", label, pct));
+        f.render_widget(gauge, *area);
+    }
+}
+
+fn draw_compact_stats(f: &mut Frame, area: Rect, app: &App) {
+    let sys = &app.state.system;
+    let mem_pct = percent(sys.used_kb, sys.total_kb);
+    let swap_pct = percent(sys.swap_used_kb, sys.swap_total_kb);
+    let level = |pct| {
+        Style::default()
+            .fg(app.theme.level(pct, USAGE_WARN_PCT, USAGE_CRITICAL_PCT))
+            .add_modifier(Modifier::BOLD)
+    };
+
+    let mut spans = vec![
+        Span::styled("Mem ", Style::default().fg(app.theme.secondary)),
+        Span::raw(format!(
+            "{} / {}{} ",
+            fmt_kb(sys.used_kb, app.unit),
+            fmt_kb(sys.total_kb, app.unit),
+            if sys.cgroup_limited { " (cgroup)" } else { "" }
+        )),
+        Span::styled(format!("{:.1}%", mem_pct), level(mem_pct)),
+        Span::styled(" | Swap ", Style::default().fg(app.theme.secondary)),
+        Span::raw(format!("{} ", fmt_kb(sys.swap_used_kb, app.unit))),
+        Span::styled(format!("{:.1}%", swap_pct), level(swap_pct)),
+        Span::styled(" | PSS ", Style::default().fg(app.theme.secondary)),
+        Span::raw(format!("{} | ", fmt_kb(sys.total_process_pss_kb, app.unit))),
+    ];
+    spans.extend(session_line(app).spans);
+
+    f.render_widget(Paragraph::new(Line::from(spans)), area);
+}
+
+fn session_line(app: &App) -> Line<'static> {
+    let mut spans = vec![Span::raw(format!(
+        "{} | session {}",
+        clock::time_of_day(clock::unix_now()),
+        clock::format_elapsed(app.started.elapsed().as_secs())
+    ))];
+
+    if app.failures > 0 {
+        spans.push(Span::styled(
+            format!(" | {} failed refreshes", app.failures),
+            Style::default().fg(app.theme.error).add_modifier(Modifier::BOLD),
+        ));
+    }
+
+    let age = app.last_update.elapsed();
+    if !app.is_paused() && age > app.stale_after {
+        spans.push(Span::styled(
+            format!(" | data {} old", clock::format_elapsed(age.as_secs())),
+            Style::default().fg(app.theme.warn).add_modifier(Modifier::BOLD),
+        ));
+    }
+
+    Line::from(spans)
+}
+
+// htop-style stacked meter: used (excluding cache), cache+buffers, then free.
+// Each segment has its own glyph so the split stays readable in mono.
+fn memory_meter(app: &App, width: u16) -> Line<'static> {
+    let sys = &app.state.system;
+    let cache = sys.cached_kb + sys.buffers_kb;
+    let used = sys.total_kb.saturating_sub(sys.free_kb + cache);
+
+    let legend = format!(
+        " used {} | cache {} | free {}",
+        fmt_kb(used, app.unit),
+        fmt_kb(cache, app.unit),
+        fmt_kb(sys.free_kb, app.unit),
+    );
+    let bar_width = (width as usize).saturating_sub(legend.chars().count());
+
+    let cells = |kb: u64| {
+        if sys.total_kb > 0 {
+            (kb as f64 / sys.total_kb as f64 * bar_width as f64).round() as usize
+        } else {
+            0
+        }
+    };
+    let used_cells = cells(used).min(bar_width);
+    let cache_cells = cells(cache).min(bar_width - used_cells);
+    let free_cells = bar_width - used_cells - cache_cells;
+
+    Line::from(vec![
+        Span::styled("█".repeat(used_cells), Style::default().fg(app.theme.used)),
+        Span::styled("▒".repeat(cache_cells), Style::default().fg(app.theme.cache)),
+        Span::styled("░".repeat(free_cells), Style::default().fg(app.theme.free)),
+        Span::styled(legend, Style::default().fg(app.theme.secondary)),
+    ])
+}
+
+// The header gauges' colors once usage crosses their warning threshold. The
+// Free bar goes by used memory rather than MemFree, which the page cache
+// keeps low on a healthy system.
+fn pressure_color(app: &App, used_pct: f64) -> ratatui::style::Color {
+    if used_pct >= USAGE_WARN_PCT {
+        app.theme.level(used_pct, USAGE_WARN_PCT, USAGE_CRITICAL_PCT)
+    } else {
+        app.theme.secondary
+    }
+}
+
+fn percent(part: u64, total: u64) -> f64 {
+    if total > 0 {
+        (part as f64 / total as f64) * 100.0
+    } else {
+        0.0
+    }
+}
+
+fn draw_process_list(f: &mut Frame, area: Rect, app: &App) {
+    let header_cells = app.columns.iter().map(|c| {
+        ratatui::text::Text::from(c.header()).style(
+            Style::default()
+                .fg(app.theme.secondary)
+                .add_modifier(Modifier::BOLD),
+        )
+    });
+
+    let header = Row::new(header_cells).height(1).bottom_margin(1);
+
+    let rows: Vec<Row> = app.processes
+        .iter()
+        .skip(app.scroll_offset)
+        .take(app.visible_rows)
+        .enumerate()
+        .map(|(i, proc)| {
+            let watched = app.watchlist.contains(&proc.pid);
+            let cells = app.columns.iter().enumerate().map(|(col, &c)| {
+                let cell = process_cell(app, proc, c);
+                let marker = if col == 0 && watched { "*" } else { "" };
+
+                if c == Column::Name && !app.name_filter.is_empty() {
+                    return Cell::from(highlight_match(app, marker, &cell));
+                }
+                let cell = format!("{}{}", marker, cell);
+
+                match (c, delta_kb(app, proc)) {
+                    (Column::Delta, Some(delta)) if delta > 0 => {
+                        Cell::from(cell).style(Style::default().fg(app.theme.grow))
+                    }
+                    (Column::Delta, Some(delta)) if delta < 0 => {
+                        Cell::from(cell).style(Style::default().fg(app.theme.shrink))
+                    }
+                    _ => Cell::from(cell),
+                }
+            });
+
+            Row::new(cells)
+                .style({
+                    let mut style = if proc.pss_delta_kb.unsigned_abs() > app.bold_delta_kb {
+                        Style::default().add_modifier(Modifier::BOLD)
+                    } else {
+                        Style::default()
+                    };
+
+                    if proc.is_kernel_thread || (proc.rss_only && !app.fast) {
+                        style = style.add_modifier(Modifier::DIM);
+                    }
+
+                    if proc.suspected_leak {
+                        style = style.fg(app.theme.leak);
+                    }
+
+                    if app.alerted.contains(&proc.pid) {
+                        style = style.fg(app.theme.alert).add_modifier(Modifier::BOLD);
+                    }
+
+                    if app.scroll_offset + i == app.selected {
+                        style.add_modifier(Modifier::REVERSED)
+                    } else {
+                        style
+                    }
+                })
+        })
+        .collect();
+
+    let sort_indicator = sort_indicator(sort_label(app.sort_mode), app.ascending);
+
+    let mut filter_indicator = match &app.cgroup_filter {
+        Some(cg) if cg.is_empty() => String::from(" [cgroup: none]"),
+        Some(cg) => format!(" [cgroup: {}]", cg),
+        None => String::new(),
+    };
+
+    if let Some((_, user)) = &app.user_filter {
+        filter_indicator.push_str(&format!(" [user: {}]", user));
+    }
+
+    if app.editing_filter {
+        filter_indicator.push_str(&format!(" [/{}_]", app.name_filter));
+    } else if !app.name_filter.is_empty() {
+        filter_indicator.push_str(&format!(" [name: {}]", app.name_filter));
+    }
+
+    if app.hide_kernel_threads {
+        filter_indicator.push_str(" [no kernel threads]");
+    }
+
+    if let Some(threshold) = app.alert_pss_kb {
+        let basis = if app.fast {
+            "RSS "
+        } else if app.state.processes.iter().any(|p| p.rss_only) {
+            "PSS/RSS "
+        } else {
+            ""
+        };
+        filter_indicator.push_str(&format!(
+            " [{}over {}: {}]",
+            basis,
+            fmt_kb(threshold, app.unit),
+            app.alerted.len()
+        ));
+    }
+
+    if !app.watchlist.is_empty() {
+        filter_indicator.push_str(&format!(" [watching: {}]", app.watchlist.len()));
+    }
+
+    let baseline_indicator = match app.active_baseline() {
+        Some(baseline) => format!(" [Delta vs baseline {}]", clock::time_of_day(baseline.captured_at)),
+        None => String::new(),
+    };
+
+    let mut skipped_indicator = match app.state.processes_skipped {
+        0 => String::new(),
+        n => format!(" [{} exited mid-scan]", n),
+    };
+    if app.no_smaps_rollup {
+        skipped_indicator.push_str(" [RSS only: no smaps_rollup on this kernel (needs 4.14+), PSS unavailable]");
+    } else if app.fast {
+        skipped_indicator.push_str(" [fast: RSS only, PSS unavailable]");
+    }
+
+    let title = format!(
+        "Processes ({}/{}){}{}{}{}",
+        app.scroll_offset.min(app.processes.len()),
+        app.processes.len(),
+        skipped_indicator,
+        sort_indicator,
+        filter_indicator,
+        baseline_indicator
+    );
+
+    let block = Block::default().borders(Borders::ALL).title(title);
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let parts = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(TOTAL_ROW_LINES)])
+        .split(inner);
+
+    let table = Table::new(rows, column_constraints(app, inner.width)).header(header);
+    f.render_widget(table, parts[0]);
+
+    // Sums over the filtered list, pinned below it and never scrolled.
+    let total_cells = app.columns.iter().map(|&c| total_cell(app, c));
+    let total = Table::new(
+        [Row::new(total_cells).style(
+            Style::default()
+                .fg(app.theme.secondary)
+                .add_modifier(Modifier::BOLD),
+        )],
+        column_constraints(app, inner.width),
+    )
+    .block(Block::default().borders(Borders::TOP));
+    f.render_widget(total, parts[1]);
+}
+
+// Full command lines take whatever width the other columns and the one-cell
+// gaps between them leave, but never less than the usual minimum.
+fn column_constraints(app: &App, width: u16) -> Vec<Constraint> {
+    let others: u16 = app
+        .columns
+        .iter()
+        .filter(|&&c| c != Column::Name)
+        .map(|c| match c.constraint() {
+            Constraint::Length(n) | Constraint::Min(n) => n + 1,
+            _ => 1,
+        })
+        .sum();
+
+    app.columns
+        .iter()
+        .map(|&c| match (c, c.constraint()) {
+            (Column::Name, Constraint::Min(min)) if app.full_names => {
+                Constraint::Min(width.saturating_sub(others).max(min))
+            }
+            (_, constraint) => constraint,
+        })
+        .collect()
+}
+
+fn total_cell(app: &App, column: Column) -> String {
+    let procs = &app.processes;
+    let sum = |kb: fn(&ProcessStats) -> u64| fmt_kb(procs.iter().map(kb).sum(), app.unit);
+
+    match column {
+        _ if app.fast && column.needs_smaps() => String::from("n/a"),
+        Column::Pid if !app.columns.contains(&Column::Name) => String::from("Total"),
+        Column::Name => format!("Total ({})", procs.len()),
+        Column::Pid | Column::User | Column::Oom | Column::Age => String::new(),
+        Column::Pss => sum(|p| p.pss_kb),
+        Column::MemPct => format!("{:.1}", procs.iter().map(|p| p.mem_pct).sum::<f64>()),
+        // Rows without a PSS would count as fully shared.
+        Column::Sharing => {
+            let measured = procs.iter().filter(|p| !p.rss_only);
+            format!(
+                "{:.1}",
+                analyzer::sharing_pct(
+                    measured.clone().map(|p| p.rss_kb).sum(),
+                    measured.map(|p| p.pss_kb).sum()
+                )
+            )
+        }
+        Column::Rss => sum(|p| p.rss_kb),
+        Column::Shared => sum(|p| p.shared_kb),
+        Column::SharedClean => sum(|p| p.shared_clean_kb),
+        Column::SharedDirty => sum(|p| p.shared_dirty_kb),
+        Column::Private => sum(|p| p.private_kb),
+        Column::PrivateClean => sum(|p| p.private_clean_kb),
+        Column::PrivateDirty => sum(|p| p.private_dirty_kb),
+        Column::Swap => sum(|p| p.swap_kb),
+        Column::Thp => sum(|p| p.anon_huge_kb),
+        Column::Threads => procs.iter().map(|p| p.threads).sum::<u32>().to_string(),
+        Column::Delta => {
+            let delta: i64 = procs.iter().filter_map(|p| delta_kb(app, p)).sum();
+            if app.delta_in_kb {
+                fmt_signed_exact_kb(delta)
+            } else {
+                fmt_signed_kb(delta, app.unit)
+            }
+        }
+        Column::Growth => format!("{:+.1}", procs.iter().map(|p| p.growth_rate_kb_s).sum::<f64>()),
+    }
+}
+
+fn process_cell(app: &App, proc: &ProcessStats, column: Column) -> String {
+    match column {
+        _ if proc.rss_only && column.needs_smaps() => String::from("n/a"),
+        Column::Pid => proc.pid.to_string(),
+        Column::User => proc.username.clone(),
+        Column::Name if app.full_names => proc.cmdline.clone(),
+        Column::Name => display_name(proc).to_string(),
+        Column::Pss => fmt_kb(proc.pss_kb, app.unit),
+        Column::MemPct => format!("{:.1}", proc.mem_pct),
+        Column::Sharing => format!("{:.1}", proc.sharing_pct),
+        Column::Rss => fmt_kb(proc.rss_kb, app.unit),
+        Column::Shared => fmt_kb(proc.shared_kb, app.unit),
+        Column::SharedClean => fmt_kb(proc.shared_clean_kb, app.unit),
+        Column::SharedDirty => fmt_kb(proc.shared_dirty_kb, app.unit),
+        Column::Private => fmt_kb(proc.private_kb, app.unit),
+        Column::PrivateClean => fmt_kb(proc.private_clean_kb, app.unit),
+        Column::PrivateDirty => fmt_kb(proc.private_dirty_kb, app.unit),
+        Column::Swap => fmt_kb(proc.swap_kb, app.unit),
+        Column::Oom => proc.oom_score.to_string(),
+        Column::Threads => proc.threads.to_string(),
+        Column::Delta => match delta_kb(app, proc) {
+            Some(delta) if app.delta_in_kb => fmt_signed_exact_kb(delta),
+            Some(delta) => fmt_signed_kb(delta, app.unit),
+            None if app.active_baseline().is_some() => String::from("new"),
+            None => String::from("-"),
+        },
+        Column::Growth => format!("{:+.1}", proc.growth_rate_kb_s),
+        Column::Age => clock::format_age(proc.age_secs),
+        Column::Thp => fmt_kb(proc.anon_huge_kb, app.unit),
+    }
+}
+
+// ASCII-only case folding keeps byte offsets valid in the original text.
+fn find_match(text: &str, query: &str) -> Option<usize> {
+    text.to_ascii_lowercase().find(&query.to_ascii_lowercase())
+}
+
+fn highlight_match<'a>(app: &App, marker: &'a str, text: &str) -> Line<'a> {
+    let Some(start) = find_match(text, &app.name_filter) else {
+        return Line::from(format!("{}{}", marker, text));
+    };
+    let end = start + app.name_filter.len();
+
+    Line::from(vec![
+        Span::raw(marker),
+        Span::raw(text[..start].to_string()),
+        Span::styled(
+            text[start..end].to_string(),
+            Style::default()
+                .fg(app.theme.warn)
+                .add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+        ),
+        Span::raw(text[end..].to_string()),
+    ])
+}
+
+// Rows read from status alone have no PSS, so --alert-pss falls back to
+// their RSS rather than never firing for them.
+fn alert_kb(proc: &ProcessStats) -> u64 {
+    if proc.rss_only { proc.rss_kb } else { proc.pss_kb }
+}
+
+// PSS change against the baseline when one is shown, otherwise since the
+// previous tick. None means there is nothing to compare against.
+fn delta_kb(app: &App, proc: &ProcessStats) -> Option<i64> {
+    match app.active_baseline() {
+        Some(baseline) => baseline
+            .pss_kb
+            .get(&(proc.pid, proc.start_time_ticks))
+            .map(|&base| proc.pss_kb as i64 - base as i64),
+        None if proc.pss_delta_kb != 0 => Some(proc.pss_delta_kb),
+        None => None,
+    }
+}
+
+fn sort_label(mode: SortMode) -> &'static str {
+    match mode {
+        SortMode::Pss => "PSS",
+        SortMode::Rss => "RSS",
+        SortMode::Shared => "Shared",
+        SortMode::Swap => "Swap",
+        SortMode::PrivateDirty => "Private Dirty",
+        SortMode::Pid => "PID",
+        SortMode::OomScore => "OOM",
+        SortMode::Threads => "Threads",
+        SortMode::Growth => "Growth",
+        SortMode::Age => "Age",
+        SortMode::Thp => "THP",
+    }
+}
+
+fn sort_indicator(label: &str, ascending: bool) -> String {
+    format!(" [Sort: {} {}]", label, if ascending { "↑" } else { "↓" })
+}
+
+fn sort_groups(groups: &mut [GroupedStats], mode: SortMode) {
+    // Also the tiebreaker for the other modes; "PID" order for groups is
+    // by name, which the presort already gives.
+    groups.sort_by(|a, b| a.name.cmp(&b.name));
+    match mode {
+        SortMode::Pss
+        | SortMode::OomScore
+        | SortMode::Threads
+        | SortMode::Growth
+        | SortMode::Age
+        | SortMode::Thp => {
+            groups.sort_by_key(|g| std::cmp::Reverse(g.pss_kb))
+        }
+        SortMode::Rss => groups.sort_by_key(|g| std::cmp::Reverse(g.rss_kb)),
+        SortMode::Shared => groups.sort_by_key(|g| std::cmp::Reverse(g.shared_kb)),
+        SortMode::Swap => groups.sort_by_key(|g| std::cmp::Reverse(g.swap_kb)),
+        SortMode::PrivateDirty => groups.sort_by_key(|g| std::cmp::Reverse(g.private_kb)),
+        SortMode::Pid => {}
+    }
+}
+
+fn draw_grouped_list(
+    f: &mut Frame,
+    area: Rect,
+    app: &App,
+    groups: &[GroupedStats],
+    title: &str,
+    label: &str,
+) {
+    let header_cells = [label, "Count", "PSS", "RSS", "Shared", "Private", "Swap"]
+        .into_iter()
+        .map(|h| {
+            ratatui::text::Text::from(h).style(
+                Style::default()
+                    .fg(app.theme.secondary)
+                    .add_modifier(Modifier::BOLD),
+            )
+        });
+
+    let header = Row::new(header_cells).height(1).bottom_margin(1);
+
+    let rows: Vec<Row> = groups
+        .iter()
+        .skip(app.scroll_offset)
+        .take(app.visible_rows)
+        .map(|group| {
+            Row::new(vec![
+                group.name.clone(),
+                group.count.to_string(),
+                fmt_kb(group.pss_kb, app.unit),
+                fmt_kb(group.rss_kb, app.unit),
+                fmt_kb(group.shared_kb, app.unit),
+                fmt_kb(group.private_kb, app.unit),
+                fmt_kb(group.swap_kb, app.unit),
+            ])
+        })
+        .collect();
+
+    let label = match app.sort_mode {
+        SortMode::Pid => "Name",
+        SortMode::PrivateDirty => "Private",
+        SortMode::OomScore | SortMode::Threads | SortMode::Growth | SortMode::Age | SortMode::Thp => {
+            "PSS"
+        }
+        mode => sort_label(mode),
+    };
+    let sort_indicator = sort_indicator(label, app.ascending);
+
+    let title = format!(
+        "{} ({}/{}){}",
+        title,
+        app.scroll_offset.min(groups.len()),
+        groups.len(),
+        sort_indicator
+    );
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Min(20),
+            Constraint::Length(7),
+            Constraint::Length(11),
+            Constraint::Length(11),
+            Constraint::Length(11),
+            Constraint::Length(11),
+            Constraint::Length(11),
+        ],
+    )
+    .header(header)
+    .block(Block::default().borders(Borders::ALL).title(title));
+
+    f.render_widget(table, area);
+}
+
+// Newest sample at the right edge, so a short history grows in from the
+// right instead of stretching across the whole width.
+fn draw_usage_chart(f: &mut Frame, area: Rect, app: &App) {
+    let history = &app.state.used_pct_history;
+    let offset = (SYSTEM_HISTORY_LEN - history.len()) as f64;
+    let points: Vec<(f64, f64)> = history
+        .iter()
+        .enumerate()
+        .map(|(i, &pct)| (offset + i as f64, pct))
+        .collect();
+
+    let current = history.last().copied().unwrap_or(0.0);
+    let min = history.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = history.iter().copied().fold(0.0, f64::max);
+    let title = if history.is_empty() {
+        String::from("Memory used")
+    } else {
+        format!(
+            "Memory used, last {} samples (now {:.1}%, min {:.1}%, max {:.1}%)",
+            history.len(),
+            current,
+            min,
+            max
+        )
+    };
+
+    let color = app.theme.level(current, USAGE_WARN_PCT, USAGE_CRITICAL_PCT);
+    let dataset = Dataset::default()
+        .marker(Marker::Braille)
+        .graph_type(GraphType::Line)
+        .style(Style::default().fg(color))
+        .data(&points);
+
+    let label_style = Style::default().fg(app.theme.secondary);
+    let chart = Chart::new(vec![dataset])
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .x_axis(Axis::default().bounds([0.0, (SYSTEM_HISTORY_LEN - 1) as f64]))
+        .y_axis(
+            Axis::default()
+                .bounds([0.0, 100.0])
+                .labels(vec![
+                    Span::styled("0%", label_style),
+                    Span::styled("50%", label_style),
+                    Span::styled("100%", label_style),
+                ]),
+        );
+
+    f.render_widget(chart, area);
+}
+
+// A count that keeps climbing next to climbing memory points at a fork
+// bomb or leaked children rather than one process growing.
+fn draw_process_count_chart(f: &mut Frame, area: Rect, app: &App) {
+    let history = &app.state.process_count_history;
+    let offset = (SYSTEM_HISTORY_LEN - history.len()) as f64;
+    let points: Vec<(f64, f64)> = history
+        .iter()
+        .enumerate()
+        .map(|(i, &count)| (offset + i as f64, count as f64))
+        .collect();
+
+    let current = history.last().copied().unwrap_or(0);
+    let min = history.iter().copied().min().unwrap_or(0);
+    let max = history.iter().copied().max().unwrap_or(0);
+    let title = if history.is_empty() {
+        String::from("Processes")
+    } else {
+        format!("Processes (now {}, min {}, max {})", current, min, max)
+    };
+
+    // Headroom above the maximum keeps the line off the top border.
+    let top = (max as f64 * 1.2).max(1.0);
+    let dataset = Dataset::default()
+        .marker(Marker::Braille)
+        .graph_type(GraphType::Line)
+        .style(Style::default().fg(app.theme.primary))
+        .data(&points);
+
+    let label_style = Style::default().fg(app.theme.secondary);
+    let chart = Chart::new(vec![dataset])
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .x_axis(Axis::default().bounds([0.0, (SYSTEM_HISTORY_LEN - 1) as f64]))
+        .y_axis(
+            Axis::default()
+                .bounds([0.0, top])
+                .labels(vec![
+                    Span::styled("0", label_style),
+                    Span::styled(format!("{:.0}", top), label_style),
+                ]),
+        );
+
+    f.render_widget(chart, area);
+}
+
+fn draw_memory_map(f: &mut Frame, area: Rect, app: &App) {
+    let map = &app.state.memory_map;
+    let sys = &app.state.system;
+
+    // The map breaks down /proc/meminfo, so it is always against the host
+    // total even when the header shows a cgroup limit.
+    let total = sys.host_total_kb as f64;
+
+    let heading = match app.bar_scale {
+        BarScale::Linear => String::from("Physical Memory Distribution:"),
+        scale => format!("Physical Memory Distribution ({} bars, l to change):", scale.label()),
+    };
+    let mut lines = vec![
+        Line::from(Span::styled(
+            heading,
+            Style::default().fg(app.theme.secondary).add_modifier(Modifier::BOLD),
+        )),
+        Line::from(""),
+    ];
+
+    let mut items = vec![
+        ("Kernel", map.kernel_kb, app.theme.secondary),
+        ("Process Private", map.process_private_kb, app.theme.primary),
+        ("Process Shared", map.process_shared_kb, app.theme.primary),
+        ("Page Cache", map.cache_kb, app.theme.primary),
+        ("Buffers", map.buffers_kb, app.theme.primary),
+        ("Slab", map.slab_kb, app.theme.primary),
+        ("Page Tables", map.page_tables_kb, app.theme.primary),
+        ("Free", map.free_kb, pressure_color(app, percent(sys.used_kb, sys.total_kb))),
+    ];
+
+    if map.hugepages_used_kb + map.hugepages_free_kb > 0 {
+        items.push(("HugePages Used", map.hugepages_used_kb, app.theme.primary));
+        items.push(("HugePages Free", map.hugepages_free_kb, app.theme.secondary));
+    }
+
+    let pct_of_total = |kb: u64| if total > 0.0 { (kb as f64 / total) * 100.0 } else { 0.0 };
+    let max_pct = items.iter().map(|&(_, kb, _)| pct_of_total(kb)).fold(0.0, f64::max);
+
+    for (label, kb, color) in items {
+        let pct = pct_of_total(kb);
+        let bar = "#".repeat(app.bar_scale.width(pct, max_pct, 50.0));
+
+        lines.push(Line::from(vec![
+            Span::styled(format!("{:16} ", label), Style::default().fg(color)),
+            Span::raw(format!("{:>10} ({:5.1}%) ", fmt_kb(kb, app.unit), pct)),
+            Span::styled(bar, Style::default().fg(color)),
+        ]));
+    }
+
+    // Swap isn't part of physical memory, so its bar is against swap size,
+    // and relative scaling leaves it linear rather than comparing the two.
+    if sys.swap_total_kb > 0 {
+        let pct = percent(sys.swap_used_kb, sys.swap_total_kb);
+        let color = pressure_color(app, pct);
+        lines.push(Line::from(vec![
+            Span::styled(format!("{:16} ", "Swap Used"), Style::default().fg(color)),
+            Span::raw(format!("{:>10} ({:5.1}% of swap) ", fmt_kb(sys.swap_used_kb, app.unit), pct)),
+            Span::styled("#".repeat(app.bar_scale.width(pct, 100.0, 50.0)), Style::default().fg(color)),
+        ]));
+    }
+
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![
+        Span::styled("Total: ", Style::default().fg(app.theme.secondary)),
+        Span::raw(fmt_kb(sys.host_total_kb, app.unit)),
+    ]));
+
+    if map.anon_hugepages_kb > 0 {
+        lines.push(Line::from(vec![
+            Span::styled("Transparent HugePages: ", Style::default().fg(app.theme.secondary)),
+            Span::raw(format!(
+                "{} (included in process memory), {} attributed to processes",
+                fmt_kb(map.anon_hugepages_kb, app.unit),
+                fmt_kb(sys.total_process_anon_huge_kb, app.unit)
+            )),
+        ]));
+    }
+
+    if map.dirty_kb + map.writeback_kb > 0 {
+        // Dirty pages must be written back before they can be reclaimed, so a
+        // large backlog under pressure is a common cause of stalls.
+        let style = match &sys.pressure {
+            Some(psi) if psi.some_avg10 > 0.0 => Style::default().fg(app.theme.warn),
+            _ => Style::default(),
+        };
+        lines.push(Line::from(vec![
+            Span::styled("Dirty/Writeback: ", Style::default().fg(app.theme.secondary)),
+            Span::styled(
+                format!(
+                    "{} dirty, {} under writeback (included in page cache)",
+                    fmt_kb(map.dirty_kb, app.unit),
+                    fmt_kb(map.writeback_kb, app.unit)
+                ),
+                style,
+            ),
+        ]));
+    }
+
+    let discrepancy = match map.reconciliation_error_kb {
+        Some(error_kb) if error_kb.abs() > RECONCILIATION_WARN_KB => Span::styled(
+            format!("discrepancy {}", fmt_signed_kb(error_kb, app.unit)),
+            Style::default().fg(app.theme.error).add_modifier(Modifier::BOLD),
+        ),
+        Some(error_kb) => Span::raw(format!("discrepancy {}", fmt_signed_kb(error_kb, app.unit))),
+        None => Span::styled(
+            "n/a (needs the PSS of every process)",
+            Style::default().add_modifier(Modifier::DIM),
+        ),
+    };
+
+    lines.push(Line::from(vec![
+        Span::styled("Reconciliation: ", Style::default().fg(app.theme.secondary)),
+        Span::raw(format!(
+            "process PSS {} vs kernel AnonPages + Mapped {}, ",
+            fmt_kb(map.process_pss_kb, app.unit),
+            fmt_kb(map.anon_mapped_kb, app.unit)
+        )),
+        discrepancy,
+    ]));
+
+    if !app.state.numa_nodes.is_empty() {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            "NUMA Nodes:",
+            Style::default().fg(app.theme.secondary).add_modifier(Modifier::BOLD),
+        )));
+
+        for node in &app.state.numa_nodes {
+            let used_pct = if node.mem_total_kb > 0 {
+                (node.mem_used_kb as f64 / node.mem_total_kb as f64) * 100.0
+            } else {
+                0.0
+            };
+
+            lines.push(Line::from(vec![
+                Span::raw(format!("  Node {}: ", node.node_id)),
+                Span::raw(format!(
+                    "{} / {} ({:.1}%)",
+                    fmt_kb(node.mem_used_kb, app.unit),
+                    fmt_kb(node.mem_total_kb, app.unit),
+                    used_pct
+                )),
+            ]));
+        }
+    }
+
+    let para = Paragraph::new(lines)
+        .block(Block::default().borders(Borders::ALL).title("Physical Memory Map"));
+    f.render_widget(para, area);
+}
+
+fn draw_shared_view(f: &mut Frame, area: Rect, app: &App) {
+    let shared = &app.state.shared_memory;
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(9), Constraint::Min(0)])
+        .split(area);
+
+    let lines = vec![
+        Line::from(vec![
+            Span::styled("Total Shared Memory: ", Style::default().fg(app.theme.secondary)),
+            Span::raw(fmt_kb(shared.total_shared_kb, app.unit)),
+        ]),
+        Line::from(vec![
+            Span::styled("  Clean: ", Style::default().fg(app.theme.primary)),
+            Span::raw(fmt_kb(shared.total_shared_clean_kb, app.unit)),
+        ]),
+        Line::from(vec![
+            Span::styled("  Dirty: ", Style::default().fg(app.theme.primary)),
+            Span::raw(fmt_kb(shared.total_shared_dirty_kb, app.unit)),
+        ]),
+        Line::from(vec![
+            Span::styled("Sharing Efficiency: ", Style::default().fg(app.theme.secondary)),
+            Span::raw(if app.fast {
+                String::from("n/a")
+            } else {
+                format!("{:.1}%", shared.sharing_efficiency)
+            }),
+        ]),
+        Line::from(vec![
+            Span::styled("Shmem (tmpfs + shm): ", Style::default().fg(app.theme.secondary)),
+            Span::raw(format!(
+                "{} | SysV segments: {} ({})",
+                fmt_kb(shared.shmem_kb, app.unit),
+                shared.shm_segments.len(),
+                fmt_kb(shared.sysv_shm_kb, app.unit)
+            )),
+        ]),
+        Line::from(""),
+        Line::from(Span::styled(
+            "Memory saved by sharing pages across processes",
+            Style::default().fg(app.theme.primary),
+        )),
+    ];
 
     let para = Paragraph::new(lines)
         .block(Block::default().borders(Borders::ALL).title("Shared Memory Analysis"));
+    f.render_widget(para, chunks[0]);
+
+    draw_shm_segments(f, chunks[1], app);
+}
+
+fn draw_shm_segments(f: &mut Frame, area: Rect, app: &App) {
+    let segments = &app.state.shared_memory.shm_segments;
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!("SysV Shared Memory Segments ({})", segments.len()));
+
+    if segments.is_empty() {
+        let para = Paragraph::new("No SysV shared memory segments (/proc/sysvipc/shm is empty or absent)")
+            .block(block);
+        f.render_widget(para, area);
+        return;
+    }
+
+    let header_cells = ["Key", "shmid", "Size", "RSS", "Swap", "Attached", "Creator", "Owner"]
+        .into_iter()
+        .map(|h| {
+            ratatui::text::Text::from(h).style(
+                Style::default()
+                    .fg(app.theme.secondary)
+                    .add_modifier(Modifier::BOLD),
+            )
+        });
+
+    let header = Row::new(header_cells).height(1).bottom_margin(1);
+
+    let rows: Vec<Row> = segments
+        .iter()
+        .map(|segment| {
+            Row::new(vec![
+                format!("0x{:08x}", segment.key),
+                segment.shmid.to_string(),
+                fmt_kb(segment.size_kb, app.unit),
+                fmt_kb(segment.rss_kb, app.unit),
+                fmt_kb(segment.swap_kb, app.unit),
+                segment.attached.to_string(),
+                segment.creator_pid.to_string(),
+                segment.owner.clone(),
+            ])
+        })
+        .collect();
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(11),
+            Constraint::Length(10),
+            Constraint::Length(11),
+            Constraint::Length(11),
+            Constraint::Length(11),
+            Constraint::Length(9),
+            Constraint::Length(8),
+            Constraint::Min(10),
+        ],
+    )
+    .header(header)
+    .block(block);
+
+    f.render_widget(table, area);
+}
+
+fn draw_tree_view(f: &mut Frame, area: Rect, app: &App) {
+    let tree = app.tree_rows();
+    let procs: HashMap<u32, &ProcessStats> =
+        app.state.processes.iter().map(|p| (p.pid, p)).collect();
+
+    let header_cells = ["PID", "User", "Name", "PSS", "Subtree PSS", "RSS", "Procs"]
+        .into_iter()
+        .map(|h| {
+            ratatui::text::Text::from(h).style(
+                Style::default()
+                    .fg(app.theme.secondary)
+                    .add_modifier(Modifier::BOLD),
+            )
+        });
+
+    let header = Row::new(header_cells).height(1).bottom_margin(1);
+
+    let rows: Vec<Row> = tree
+        .iter()
+        .enumerate()
+        .skip(app.scroll_offset)
+        .take(app.visible_rows)
+        .filter_map(|(i, row)| {
+            let proc = procs.get(&row.pid)?;
+            let marker = match row.descendants {
+                0 => "  ",
+                _ if app.collapsed.contains(&row.pid) => "▸ ",
+                _ => "▾ ",
+            };
+            let pss = if proc.rss_only {
+                String::from("n/a")
+            } else {
+                fmt_kb(proc.pss_kb, app.unit)
+            };
+
+            let mut style = Style::default();
+            if proc.is_kernel_thread {
+                style = style.add_modifier(Modifier::DIM);
+            }
+            if i == app.tree_selected {
+                style = style.add_modifier(Modifier::REVERSED);
+            }
+
+            Some(
+                Row::new(vec![
+                    proc.pid.to_string(),
+                    proc.username.clone(),
+                    format!("{}{}{}", "  ".repeat(row.depth), marker, display_name(proc)),
+                    pss,
+                    fmt_kb(row.subtree_pss_kb, app.unit),
+                    fmt_kb(proc.rss_kb, app.unit),
+                    (row.descendants + 1).to_string(),
+                ])
+                .style(style),
+            )
+        })
+        .collect();
+
+    let roots = tree.iter().filter(|row| row.depth == 0).count();
+    let title = format!(
+        "Process Tree ({} shown, {} roots, Enter to expand/collapse)",
+        tree.len(),
+        roots
+    );
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(7),
+            Constraint::Length(10),
+            Constraint::Min(20),
+            Constraint::Length(11),
+            Constraint::Length(12),
+            Constraint::Length(11),
+            Constraint::Length(6),
+        ],
+    )
+    .header(header)
+    .block(Block::default().borders(Borders::ALL).title(title));
+
+    f.render_widget(table, area);
+}
+
+fn draw_security_view(f: &mut Frame, area: Rect, app: &App) {
+    let procs = app.wx_processes();
+
+    let header_cells = ["PID", "User", "Name", "W+X Regions", "W+X Size"]
+        .into_iter()
+        .map(|h| {
+            ratatui::text::Text::from(h).style(
+                Style::default()
+                    .fg(app.theme.secondary)
+                    .add_modifier(Modifier::BOLD),
+            )
+        });
+
+    let header = Row::new(header_cells).height(1).bottom_margin(1);
+
+    let rows: Vec<Row> = procs
+        .iter()
+        .skip(app.scroll_offset)
+        .take(app.visible_rows)
+        .map(|proc| {
+            Row::new(vec![
+                proc.pid.to_string(),
+                proc.username.clone(),
+                proc.name.clone(),
+                proc.wx_regions.to_string(),
+                fmt_kb(proc.wx_kb, app.unit),
+            ])
+            .style(Style::default().fg(app.theme.warn))
+        })
+        .collect();
+
+    let title = format!(
+        "Writable+Executable Mappings ({} of {} processes)",
+        procs.len(),
+        app.state.processes.len()
+    );
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(7),
+            Constraint::Length(10),
+            Constraint::Min(20),
+            Constraint::Length(12),
+            Constraint::Length(11),
+        ],
+    )
+    .header(header)
+    .block(Block::default().borders(Borders::ALL).title(title));
+
+    f.render_widget(table, area);
+}
+
+fn draw_diff_view(f: &mut Frame, area: Rect, app: &App) {
+    let (Some(a), Some(b)) = (&app.snapshot_a, &app.snapshot_b) else {
+        return;
+    };
+    let diff = &app.snapshot_diff;
+
+    let header_cells = ["Change", "PID", "Name", "PSS at A", "PSS at B", "Delta"]
+        .into_iter()
+        .map(|h| {
+            ratatui::text::Text::from(h).style(
+                Style::default()
+                    .fg(app.theme.secondary)
+                    .add_modifier(Modifier::BOLD),
+            )
+        });
+
+    let header = Row::new(header_cells).height(1).bottom_margin(1);
+    let pss = |kb: Option<u64>| kb.map_or(String::from("-"), |kb| fmt_kb(kb, app.unit));
+
+    let rows: Vec<Row> = diff
+        .iter()
+        .skip(app.scroll_offset)
+        .take(app.visible_rows)
+        .map(|d| {
+            let (label, color) = match d.change {
+                Change::Grew => ("grew", app.theme.grow),
+                Change::Shrank => ("shrank", app.theme.shrink),
+                Change::Appeared => ("appeared", app.theme.grow),
+                Change::Disappeared => ("disappeared", app.theme.shrink),
+            };
+
+            Row::new(vec![
+                label.to_string(),
+                d.pid.to_string(),
+                d.name.clone(),
+                pss(d.pss_a_kb),
+                pss(d.pss_b_kb),
+                fmt_signed_kb(d.delta_kb, app.unit),
+            ])
+            .style(Style::default().fg(color))
+        })
+        .collect();
+
+    let count = |change: Change| diff.iter().filter(|d| d.change == change).count();
+    let title = format!(
+        "Snapshot diff A {} -> B {}: {} grew, {} shrank, {} appeared, {} disappeared, net {}",
+        clock::time_of_day(a.captured_at),
+        clock::time_of_day(b.captured_at),
+        count(Change::Grew),
+        count(Change::Shrank),
+        count(Change::Appeared),
+        count(Change::Disappeared),
+        fmt_signed_kb(diff.iter().map(|d| d.delta_kb).sum(), app.unit)
+    );
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(12),
+            Constraint::Length(7),
+            Constraint::Min(20),
+            Constraint::Length(11),
+            Constraint::Length(11),
+            Constraint::Length(12),
+        ],
+    )
+    .header(header)
+    .block(Block::default().borders(Borders::ALL).title(title));
+
+    f.render_widget(table, area);
+}
+
+fn draw_numa_view(f: &mut Frame, area: Rect, app: &App) {
+    let mut lines = Vec::new();
+
+    if app.state.numa_nodes.is_empty() {
+        lines.push(Line::from("No NUMA nodes found in /sys/devices/system/node"));
+    }
+
+    for (i, node) in app.state.numa_nodes.iter().enumerate() {
+        let consumers = app.numa_consumers(node.node_id);
+        let attributed_kb: u64 = consumers.iter().map(|&(_, kb)| kb).sum();
+        let expanded = app.numa_expanded == Some(node.node_id);
+
+        let style = if i == app.numa_selected {
+            Style::default().add_modifier(Modifier::REVERSED)
+        } else {
+            Style::default()
+        };
+
+        lines.push(Line::from(Span::styled(
+            format!(
+                "{} Node {}: {} / {} used, {} in {} processes",
+                if expanded { "▾" } else { "▸" },
+                node.node_id,
+                fmt_kb(node.mem_used_kb, app.unit),
+                fmt_kb(node.mem_total_kb, app.unit),
+                fmt_kb(attributed_kb, app.unit),
+                consumers.len()
+            ),
+            style,
+        )));
+
+        if !expanded {
+            continue;
+        }
+
+        // Kernel threads have no numa_maps and unreadable processes are
+        // skipped, so a node can hold memory that no process accounts for.
+        if consumers.is_empty() {
+            lines.push(Line::from(Span::styled(
+                "    no per-process data for this node",
+                Style::default().fg(app.theme.secondary),
+            )));
+        }
+
+        for (proc, kb) in consumers.iter().take(NUMA_TOP_CONSUMERS) {
+            lines.push(Line::from(format!(
+                "    {:>7}  {:>10}  {}",
+                proc.pid,
+                fmt_kb(*kb, app.unit),
+                proc.name
+            )));
+        }
+    }
+
+    let para = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("NUMA Nodes (Enter to expand)"),
+    );
     f.render_widget(para, area);
 }
 
+// A process far below its peak once spiked; at the peak it may still grow.
+fn peak_line(app: &App, proc: &ProcessStats) -> Line<'static> {
+    let below_kb = proc.peak_pss_kb.saturating_sub(proc.pss_kb);
+    let (note, style) = if below_kb == 0 {
+        (String::from("at peak"), Style::default().fg(app.theme.grow))
+    } else {
+        (
+            format!(
+                "{} below, now {:.0}% of peak",
+                fmt_kb(below_kb, app.unit),
+                percent(proc.pss_kb, proc.peak_pss_kb)
+            ),
+            Style::default().fg(app.theme.shrink),
+        )
+    };
+
+    Line::from(vec![
+        Span::styled(format!("{:16}", "Peak PSS"), Style::default().fg(app.theme.secondary)),
+        Span::raw(format!("{:>15} ", fmt_exact_kb(proc.peak_pss_kb))),
+        Span::styled(format!("({})", note), style),
+    ])
+}
+
+fn draw_process_detail(f: &mut Frame, app: &App) {
+    let Some(proc) = app.selected_process() else {
+        return;
+    };
+
+    // The full command line wraps below its label, growing the pane.
+    let cmdline_width = centered_rect(60, 1, f.area()).width.saturating_sub(2 + 16) as usize;
+    let command = wrap_chars(&proc.cmdline, cmdline_width, DETAIL_CMDLINE_LINES);
+    let extra_lines = command.len() as u16;
+
+    let area = centered_rect(60, 32 + extra_lines, f.area());
+    f.render_widget(Clear, area);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!("Process {} (Esc to close)", proc.pid));
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(26 + extra_lines), Constraint::Length(4)])
+        .split(inner);
+
+    let field = |label: &str, kb: u64| {
+        Line::from(vec![
+            Span::styled(format!("{:16}", label), Style::default().fg(app.theme.secondary)),
+            Span::raw(format!("{:>15}", fmt_exact_kb(kb))),
+        ])
+    };
+
+    let mut lines = vec![
+        Line::from(vec![
+            Span::styled(format!("{:16}", "Name"), Style::default().fg(app.theme.secondary)),
+            Span::raw(proc.name.clone()),
+        ]),
+        Line::from(vec![
+            Span::styled(format!("{:16}", "Executable"), Style::default().fg(app.theme.secondary)),
+            Span::raw(if proc.exe.is_empty() {
+                String::from("-")
+            } else {
+                truncate(&proc.exe, cmdline_width)
+            }),
+        ]),
+    ];
+
+    for (i, part) in command.into_iter().enumerate() {
+        let label = if i == 0 { "Command" } else { "" };
+        lines.push(Line::from(vec![
+            Span::styled(format!("{:16}", label), Style::default().fg(app.theme.secondary)),
+            Span::raw(part),
+        ]));
+    }
+
+    lines.extend([
+        Line::from(vec![
+            Span::styled(format!("{:16}", "User"), Style::default().fg(app.theme.secondary)),
+            Span::raw(format!("{} (uid {})", proc.username, proc.uid)),
+        ]),
+        Line::from(vec![
+            Span::styled(format!("{:16}", "Cgroup"), Style::default().fg(app.theme.secondary)),
+            Span::raw(if proc.cgroup.is_empty() {
+                String::from("-")
+            } else {
+                truncate(&proc.cgroup, cmdline_width)
+            }),
+        ]),
+        Line::from(""),
+        if proc.rss_only {
+            Line::from(vec![
+                Span::styled(format!("{:16}", "PSS"), Style::default().fg(app.theme.secondary)),
+                Span::raw("RSS only, PSS unavailable (smaps_rollup unreadable)"),
+            ])
+        } else {
+            field("PSS", proc.pss_kb)
+        },
+        peak_line(app, proc),
+        Line::from(vec![
+            Span::styled(format!("{:16}", "Session PSS"), Style::default().fg(app.theme.secondary)),
+            Span::raw(format!(
+                "min {}  avg {}  max {}",
+                fmt_kb(proc.min_pss_kb, app.unit),
+                fmt_kb(proc.avg_pss_kb, app.unit),
+                fmt_kb(proc.peak_pss_kb, app.unit)
+            )),
+        ]),
+        field("RSS", proc.rss_kb),
+        field("Shared Clean", proc.shared_clean_kb),
+        field("Shared Dirty", proc.shared_dirty_kb),
+        field("Private Clean", proc.private_clean_kb),
+        field("Private Dirty", proc.private_dirty_kb),
+        field("Swap", proc.swap_kb),
+        field("Swap PSS", proc.swap_pss_kb),
+        field("Anonymous", proc.anonymous_kb),
+        field("Anon HugePages", proc.anon_huge_kb),
+        field("File-backed", proc.file_backed_kb),
+        Line::from(vec![
+            Span::styled(format!("{:16}", "Sharing"), Style::default().fg(app.theme.secondary)),
+            Span::raw(if proc.rss_only {
+                String::from("n/a")
+            } else {
+                format!("{:>11.1}% of RSS", proc.sharing_pct)
+            }),
+        ]),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled(format!("{:16}", "OOM Score"), Style::default().fg(app.theme.secondary)),
+            Span::raw(format!("{:>12} (adj {:+})", proc.oom_score, proc.oom_score_adj)),
+        ]),
+        Line::from(vec![
+            Span::styled(format!("{:16}", "Threads"), Style::default().fg(app.theme.secondary)),
+            Span::raw(format!("{:>12}", proc.threads)),
+        ]),
+        Line::from(vec![
+            Span::styled(format!("{:16}", "Age"), Style::default().fg(app.theme.secondary)),
+            Span::raw(format!(
+                "{:>12} (started {})",
+                clock::format_age(proc.age_secs),
+                clock::time_of_day(clock::unix_now().saturating_sub(proc.age_secs))
+            )),
+        ]),
+        Line::from(vec![
+            Span::styled(format!("{:16}", "Minor Faults"), Style::default().fg(app.theme.secondary)),
+            Span::raw(format!("{:>12} (+{} last tick)", proc.minor_faults, proc.minor_faults_delta)),
+        ]),
+        Line::from(vec![
+            Span::styled(format!("{:16}", "Major Faults"), Style::default().fg(app.theme.secondary)),
+            Span::styled(
+                format!(
+                    "{:>12} (+{} last tick, {:.1}/s)",
+                    proc.major_faults, proc.major_faults_delta, proc.major_fault_rate
+                ),
+                if proc.major_faults_delta > 0 {
+                    Style::default().fg(app.theme.warn)
+                } else {
+                    Style::default()
+                },
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled(format!("{:16}", "Growth"), Style::default().fg(app.theme.secondary)),
+            Span::raw(format!("{:>12.1} kB/s", proc.growth_rate_kb_s)),
+            if proc.suspected_leak {
+                Span::styled("  suspected leak", Style::default().fg(app.theme.leak))
+            } else {
+                Span::raw("")
+            },
+        ]),
+    ]);
+
+    f.render_widget(Paragraph::new(lines), chunks[0]);
+
+    let min = proc.pss_history.iter().copied().min().unwrap_or(0);
+    let max = proc.pss_history.iter().copied().max().unwrap_or(0);
+    let data: Vec<u64> = proc.pss_history.iter().map(|v| v - min + 1).collect();
+
+    let sparkline = Sparkline::default()
+        .block(Block::default().borders(Borders::TOP).title(format!(
+            "PSS history, {} samples ({} - {})",
+            data.len(),
+            fmt_kb(min, app.unit),
+            fmt_kb(max, app.unit)
+        )))
+        .data(&data)
+        .style(Style::default().fg(app.theme.secondary));
+    f.render_widget(sparkline, chunks[1]);
+}
+
+// A comm at the kernel's length limit may be cut short; the executable's
+// basename disambiguates it when it starts the same way.
+fn display_name(proc: &ProcessStats) -> &str {
+    let basename = proc.exe.rsplit('/').next().unwrap_or("");
+
+    if proc.name.len() == COMM_LEN && basename.len() > COMM_LEN && basename.starts_with(&proc.name) {
+        basename
+    } else {
+        &proc.name
+    }
+}
+
+fn wrap_chars(text: &str, width: usize, max_lines: usize) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut lines: Vec<String> = chars.chunks(width.max(1)).map(|c| c.iter().collect()).collect();
+
+    if lines.len() > max_lines {
+        lines.truncate(max_lines);
+        if let Some(last) = lines.last_mut() {
+            *last = truncate(&format!("{}...", last), width);
+        }
+    }
+
+    lines
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+
+    let mut out: String = text.chars().take(max_chars.saturating_sub(3)).collect();
+    out.push_str("...");
+    out
+}
+
+fn centered_rect(percent_x: u16, height: u16, area: Rect) -> Rect {
+    let vertical = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Fill(1),
+            Constraint::Length(height.min(area.height)),
+            Constraint::Fill(1),
+        ])
+        .split(area);
+
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage((100 - percent_x) / 2),
+            Constraint::Percentage(percent_x),
+            Constraint::Percentage((100 - percent_x) / 2),
+        ])
+        .split(vertical[1])[1]
+}
+
 fn draw_help(f: &mut Frame, area: Rect, app: &App) {
-    let view_name = match app.view_mode {
-        ViewMode::Processes => "map",
-        ViewMode::MemoryMap => "shared",
-        ViewMode::SharedMemory => "process",
+    let view_name = match app.next_view() {
+        ViewMode::Processes => "process",
+        ViewMode::Tree => "tree",
+        ViewMode::Grouped => "grouped",
+        ViewMode::Users => "users",
+        ViewMode::MemoryMap => "map",
+        ViewMode::SharedMemory => "shared",
+        ViewMode::Security => "security",
+        ViewMode::Numa => "numa",
+        ViewMode::Diff => "diff",
     };
 
-    let help_text = vec![Line::from(vec![
-        Span::raw("q: quit | n: next sort | v: "),
-        Span::styled(
-            view_name,
-            Style::default().fg(COLOR_SECONDARY),
-        ),
-        Span::raw(" view | up/down: scroll | PgUp/PgDn: page"),
-    ])];
+    let enter_action = match app.view_mode {
+        ViewMode::Tree => "collapse/expand",
+        ViewMode::Numa => "expand node",
+        _ => "details",
+    };
 
-    let para = Paragraph::new(help_text)
-        .block(Block::default().borders(Borders::ALL).title("Controls"));
+    let help_text = match app.current_status() {
+        Some(message) => vec![Line::from(Span::styled(
+            message.to_string(),
+            Style::default().fg(app.theme.secondary),
+        ))],
+        None => vec![Line::from(vec![
+            Span::styled(
+                if app.is_paused() { "PAUSED" } else { "" },
+                Style::default()
+                    .fg(app.theme.secondary)
+                    .add_modifier(Modifier::BOLD | Modifier::REVERSED),
+            ),
+            Span::raw(if app.is_paused() { " " } else { "" }),
+            Span::raw("q: quit | ?: help | p: pause | n: next sort | o: reverse sort | v: "),
+            Span::styled(
+                view_name,
+                Style::default().fg(app.theme.secondary),
+            ),
+            Span::raw(" view | V: previous view | up/down: select | PgUp/PgDn: page | Home/End/g/G: top/bottom | space/F5: refresh | Enter: "),
+            Span::raw(enter_action),
+            Span::raw(" | f/U: cgroup/user filter | /: name filter | t: kernel threads | w: watch | y: copy PID | b/B: baseline | a: A/B snapshot | k/K: term/kill | u: units | c: compact | e/r: export/report | S: save preferences"),
+        ])],
+    };
+
+    let mut para = Paragraph::new(help_text);
+    if !app.compact {
+        para = para.block(Block::default().borders(Borders::ALL).title("Controls"));
+    }
     f.render_widget(para, area);
 }
\ No newline at end of file
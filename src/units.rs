@@ -0,0 +1,99 @@
+use crate::os_utils;
+use anyhow::{Result, anyhow};
+
+const KIB_PER_MIB: f64 = 1024.0;
+const KIB_PER_GIB: f64 = 1024.0 * 1024.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Unit {
+    Auto,
+    Mib,
+    Gib,
+    Pages,
+}
+
+impl Unit {
+    pub const NAMES: &'static [&'static str] = &["auto", "mib", "gib", "pages"];
+
+    pub fn by_name(name: &str) -> Result<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "auto" => Ok(Unit::Auto),
+            "mib" => Ok(Unit::Mib),
+            "gib" => Ok(Unit::Gib),
+            "pages" => Ok(Unit::Pages),
+            _ => Err(anyhow!(
+                "Unknown unit: {} (expected one of: {})",
+                name,
+                Self::NAMES.join(", ")
+            )),
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            Unit::Auto => Unit::Mib,
+            Unit::Mib => Unit::Gib,
+            Unit::Gib => Unit::Pages,
+            Unit::Pages => Unit::Auto,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Unit::Auto => "auto",
+            Unit::Mib => "mib",
+            Unit::Gib => "gib",
+            Unit::Pages => "pages",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Unit::Auto => "auto",
+            Unit::Mib => "MiB",
+            Unit::Gib => "GiB",
+            Unit::Pages => "pages",
+        }
+    }
+}
+
+pub fn fmt_kb(kb: u64, unit: Unit) -> String {
+    let value = kb as f64;
+
+    match unit {
+        Unit::Mib => format!("{:.0} MiB", value / KIB_PER_MIB),
+        Unit::Gib => format!("{:.1} GiB", value / KIB_PER_GIB),
+        // PSS splits pages between processes, so it needn't be a whole number.
+        Unit::Pages => format!("{:.0} pg", value / os_utils::page_size_kb() as f64),
+        Unit::Auto if kb < 1024 => format!("{} KiB", kb),
+        Unit::Auto if kb < 1024 * 1024 => format!("{:.1} MiB", value / KIB_PER_MIB),
+        Unit::Auto => format!("{:.1} GiB", value / KIB_PER_GIB),
+    }
+}
+
+pub fn fmt_signed_kb(kb: i64, unit: Unit) -> String {
+    let sign = if kb < 0 { '-' } else { '+' };
+    format!("{}{}", sign, fmt_kb(kb.unsigned_abs(), unit))
+}
+
+// Always a comma regardless of locale, so reports stay stable for scripts.
+pub fn group_thousands(n: u64) -> String {
+    let digits = n.to_string();
+    let mut out = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    out
+}
+
+pub fn fmt_exact_kb(kb: u64) -> String {
+    format!("{} kB", group_thousands(kb))
+}
+
+pub fn fmt_signed_exact_kb(kb: i64) -> String {
+    let sign = if kb < 0 { '-' } else { '+' };
+    format!("{}{}", sign, fmt_exact_kb(kb.unsigned_abs()))
+}
@@ -0,0 +1,136 @@
+use crate::{clock, collector::ProcessMemory};
+use anyhow::{Context, Result};
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
+enum Target {
+    // Not seen yet. With --watch-follow memz keeps waiting for the PID to
+    // appear; reported is set once the "not running" line is written.
+    Pending { pid: u32, reported: bool },
+    Tracking { pid: u32, start_time_ticks: u64 },
+    // Only with --watch-follow: the process exited and memz is waiting for
+    // one with the same name.
+    Waiting,
+    Done,
+}
+
+// Appends one line per tick for a single PID, flushed like a recording so
+// the log survives memz being killed. The name comes last since it may
+// contain spaces. The file is opened for append, so several runs can trace
+// into the same log.
+pub struct Watchpoint {
+    out: BufWriter<File>,
+    target: Target,
+    name: String,
+    follow: bool,
+}
+
+impl Watchpoint {
+    pub fn create(pid: u32, path: PathBuf, follow: bool) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("Failed to open watch log {}", path.display()))?;
+        Ok(Self {
+            out: BufWriter::new(file),
+            target: Target::Pending {
+                pid,
+                reported: false,
+            },
+            name: String::new(),
+            follow,
+        })
+    }
+
+    // A PID seen with a different start time was reused by another process,
+    // which counts as the watched one exiting.
+    pub fn write(&mut self, processes: &[ProcessMemory]) -> Result<()> {
+        let now = clock::iso_timestamp(clock::unix_now());
+
+        match self.target {
+            Target::Done => return Ok(()),
+            Target::Pending { pid, reported } => match processes.iter().find(|p| p.pid == pid) {
+                Some(proc) => {
+                    self.name = proc.name.clone();
+                    if reported {
+                        writeln!(self.out, "{} pid={} appeared name={}", now, pid, proc.name)?;
+                    }
+                    self.track(proc);
+                    self.sample(&now, proc)?;
+                }
+                None if reported => {}
+                None if self.follow => {
+                    writeln!(self.out, "{} pid={} not running, waiting", now, pid)?;
+                    self.target = Target::Pending {
+                        pid,
+                        reported: true,
+                    };
+                }
+                None => {
+                    writeln!(self.out, "{} pid={} not running", now, pid)?;
+                    self.target = Target::Done;
+                }
+            },
+            Target::Tracking {
+                pid,
+                start_time_ticks,
+            } => match processes
+                .iter()
+                .find(|p| p.pid == pid && p.start_time_ticks == start_time_ticks)
+            {
+                Some(proc) => self.sample(&now, proc)?,
+                None => {
+                    writeln!(self.out, "{} pid={} exited name={}", now, pid, self.name)?;
+                    self.target = if self.follow {
+                        Target::Waiting
+                    } else {
+                        Target::Done
+                    };
+                }
+            },
+            // The oldest match is most likely the restarted service rather
+            // than a short-lived helper sharing its name.
+            Target::Waiting => {
+                if let Some(proc) = processes
+                    .iter()
+                    .filter(|p| p.name == self.name)
+                    .min_by_key(|p| (p.start_time_ticks, p.pid))
+                {
+                    writeln!(
+                        self.out,
+                        "{} pid={} reappeared name={}",
+                        now, proc.pid, self.name
+                    )?;
+                    self.track(proc);
+                    self.sample(&now, proc)?;
+                }
+            }
+        }
+
+        self.out.flush()?;
+        Ok(())
+    }
+
+    fn track(&mut self, proc: &ProcessMemory) {
+        self.target = Target::Tracking {
+            pid: proc.pid,
+            start_time_ticks: proc.start_time_ticks,
+        };
+    }
+
+    fn sample(&mut self, now: &str, proc: &ProcessMemory) -> Result<()> {
+        let pss = if proc.rss_only {
+            String::from("n/a")
+        } else {
+            proc.pss_kb.to_string()
+        };
+        writeln!(
+            self.out,
+            "{} pid={} pss_kb={} rss_kb={} swap_kb={} name={}",
+            now, proc.pid, pss, proc.rss_kb, proc.swap_kb, proc.name
+        )?;
+        Ok(())
+    }
+}